@@ -1,5 +1,5 @@
 #![allow(non_snake_case)]
-#![feature(allocator_api, alloc_layout_extra)]
+#![feature(allocator_api)]
 //! Soa2, Soa3, ..SoaN are generic collections with an API similar to that of a Vec of tuples but which store
 //! the data laid out as a separate slice per field. The advantage of this layout is that when
 //! iterating over the data only a subset need be loaded from RAM.
@@ -7,6 +7,10 @@
 //! This approach is common to game engines, and entity component systems in particular but is
 //! applicable anywhere that cache coherency and memory bandwidth are important for performance.
 //!
+//! SmallSoa2, SmallSoa3, ..SmallSoaN store up to a fixed number of tuples inline and only allocate beyond that,
+//! for short soas which are created often.
+//! SoaArray2, SoaArray3, ..SoaArrayN have a fixed capacity, store their tuples inline and never allocate.
+//!
 //!
 //! # Example
 //! ```
@@ -51,7 +55,17 @@
 //! # Nightly
 //! This crate has strict requirements for allocations and memory layout and therefore requires the following nightly features:
 //! * allocator_api
-//! * alloc_layout_extra
+//!
+//! # Features
+//! * `serde`: Implements `Serialize` and `Deserialize` for all soas as a sequence of tuples.
+//!   A column-major format is available with `#[serde(with = "soa_vec::serde_columns")]`
+//! * `arbitrary`: Implements `Arbitrary` for all soas, for use with cargo-fuzz
+//! * `proptest`: Adds the `strategy` module with proptest strategies for generating soas
+//! * `rkyv`: Implements zero-copy archiving, where each field of an archived soa can be read as a slice
+//! * `bytemuck`: Adds `column_bytes` and `column_bytes_mut` for viewing a field of `Pod` values as bytes
+//! * `rayon`: Adds parallel iterators over the tuples of soas
+//! * `bumpalo`: Adds the `bump` module for building soas inside a bumpalo arena, including `FrameSoa` types
+//!   which never run destructors, for scratch data that is discarded with the arena
 //!
 //! # Links:
 //! * [Github source](https://github.com/That3Percent/soa-vec)
@@ -63,71 +77,439 @@
 
 
 
-use second_stack::*;
-use std::{alloc::*, cmp::*, marker::*, ptr::*, slice::*};
+use std::{alloc::*, cmp::*, convert::TryFrom, error::Error, fmt, hash::{Hash, Hasher}, iter::*, marker::*, mem::{align_of, forget, needs_drop, size_of, MaybeUninit}, ops::*, ptr::*, slice::*};
+use std::slice::{from_raw_parts, from_raw_parts_mut};
+
+/// The error type for `try_reserve` methods.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TryReserveError {
+	/// The computed capacity exceeded the maximum size of an allocation.
+	CapacityOverflow,
+	/// The allocator returned an error for the combined layout of all fields.
+	AllocError { layout: Layout },
+}
+
+impl From<LayoutError> for TryReserveError {
+	fn from(_: LayoutError) -> Self { TryReserveError::CapacityOverflow }
+}
+
+impl fmt::Display for TryReserveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TryReserveError::CapacityOverflow => write!(f, "memory allocation failed because the computed capacity exceeded the collection's maximum"),
+			TryReserveError::AllocError { .. } => write!(f, "memory allocation failed because the memory allocator returned an error"),
+		}
+	}
+}
+
+impl Error for TryReserveError {}
+
+/// The error type for constructing a soa from field slices which are not all the same length.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LengthMismatchError;
+
+impl fmt::Display for LengthMismatchError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "the field slices do not all have the same length")
+	}
+}
+
+impl Error for LengthMismatchError {}
+
+/// Column-major serialization for use with `#[serde(with = "soa_vec::serde_columns")]`.
+/// Each field is written as its own sequence, which typically compresses far better than the
+/// row-major format of the default `Serialize` implementation.
+#[cfg(feature = "serde")]
+pub mod serde_columns {
+	use serde::{Deserializer, Serializer};
+
+	/// Serializes a soa as a tuple of one sequence per field. Implemented for all soas.
+	pub trait SerializeColumns {
+		fn serialize_columns<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+	}
+
+	/// Deserializes a soa from a tuple of one sequence per field. Implemented for all soas.
+	pub trait DeserializeColumns<'de>: Sized {
+		fn deserialize_columns<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+	}
+
+	pub fn serialize<T: SerializeColumns, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+		value.serialize_columns(serializer)
+	}
+
+	pub fn deserialize<'de, T: DeserializeColumns<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+		T::deserialize_columns(deserializer)
+	}
+}
+
+/// Converts the error of a fallible allocation into the same panic or abort as Vec.
+fn handle_reserve<T>(result: Result<T, TryReserveError>) -> T {
+	match result {
+		Ok(value) => value,
+		Err(TryReserveError::CapacityOverflow) => panic!("Capacity overflow: the allocation would exceed isize::MAX bytes"),
+		Err(TryReserveError::AllocError { layout }) => handle_alloc_error(layout),
+	}
+}
+
+/// Resolves a range argument against a length, as done by Vec::drain
+///
+/// # Panics:
+///  * If the start is greater than the end or the end is greater than len
+fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+	let start = match range.start_bound() {
+		Bound::Included(&n) => n,
+		Bound::Excluded(&n) => n.checked_add(1).expect("Range start overflow"),
+		Bound::Unbounded => 0,
+	};
+	let end = match range.end_bound() {
+		Bound::Included(&n) => n.checked_add(1).expect("Range end overflow"),
+		Bound::Excluded(&n) => n,
+		Bound::Unbounded => len,
+	};
+	if start > end {
+		panic!("Range start is greater than range end");
+	}
+	if end > len {
+		panic!("Range end is out of bounds");
+	}
+	(start, end)
+}
 
 /// This macro defines a struct-of-arrays style struct.
 /// It need not be called often, just once per count of generic parameters.
 macro_rules! soa {
-	($name:ident, $L:ident, $t1:ident, $($ts:ident),+) => {
+	($name:ident, $L:ident, $Drain:ident, $ExtractIf:ident, $Splice:ident, $Ref:ident, $RefMut:ident, $Iter:ident, $IterMut:ident, $IntoIter:ident, $Chunks:ident, $ChunksExact:ident, $RChunks:ident, $Windows:ident, $ChunkBy:ident, $t1:ident, $($ts:ident),+) => {
 		/// Struct of arrays storage with vec API. See module docs for more information.
-		pub struct $name<$t1: Sized $(, $ts: Sized)*> {
+		pub struct $name<$t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
 			len: usize,
 			capacity: usize,
 			$t1: NonNull<$t1>,
 			$($ts: NonNull<$ts>,)*
 			_marker: (PhantomData<$t1> $(, PhantomData<$ts>)*),
+			/// The minimum alignment of the start of each field's buffer
+			align: usize,
+			alloc: A,
 		}
 
 		impl<$t1: Sized $(, $ts: Sized)*> $name<$t1 $(, $ts)*> {
 			/// Creates a new Soa with a capacity of 0
 			pub fn new() -> $name<$t1 $(, $ts)*> {
+				Self::new_in(Global)
+			}
+
+			/// Creates a new Soa by cloning the elements of one slice per field into a single allocation.
+			/// Returns an error if the slices are not all the same length.
+			pub fn try_from_slices(slices: (&[$t1] $(, &[$ts])*)) -> Result<Self, LengthMismatchError> where $t1: Clone $(, $ts: Clone)* {
+				let ($t1 $(, $ts)*) = slices;
+				let len = $t1.len();
+				if $($ts.len() != len)||* {
+					return Err(LengthMismatchError);
+				}
+
+				let mut soa = Self::with_capacity(len);
+				unsafe {
+					// If a clone panics the clones already written are leaked, which is safe.
+					for (i, value) in $t1.iter().enumerate() {
+						write(soa.$t1.as_ptr().add(i), value.clone());
+					}
+					$(
+						for (i, value) in $ts.iter().enumerate() {
+							write(soa.$ts.as_ptr().add(i), value.clone());
+						}
+					)*
+				}
+				soa.len = len;
+				Ok(soa)
+			}
+
+			/// Creates a new Soa with room for at least `capacity` tuples without reallocating.
+			/// All fields share a single allocation, which is made up front.
+			///
+			/// # Panics:
+			///  * If the combined size of the fields exceeds isize::MAX bytes
+			pub fn with_capacity(capacity: usize) -> $name<$t1 $(, $ts)*> {
+				Self::with_capacity_in(capacity, Global)
+			}
+
+			/// Creates a new Soa with a capacity of 0, where the start of each field's buffer will be aligned to at least align bytes.
+			/// This allows fields to be loaded with aligned SIMD instructions, and keeps fields from sharing cache lines.
+			/// Zero-sized fields never allocate, but their dangling pointers are aligned the same way.
+			///
+			/// # Panics:
+			///  * If align is not a power of two
+			pub fn with_alignment(align: usize) -> $name<$t1 $(, $ts)*> {
+				Self::with_alignment_in(align, Global)
+			}
+
+			/// Tries to create a new Soa with room for at least `capacity` tuples without reallocating.
+			/// Unlike `with_capacity`, this returns an error instead of panicking or aborting
+			/// if the capacity overflows or the allocator reports a failure.
+			pub fn try_with_capacity(capacity: usize) -> Result<$name<$t1 $(, $ts)*>, TryReserveError> {
+				Self::try_with_capacity_in(capacity, Global)
+			}
+
+			/// Decomposes the soa into its raw components: a tuple of pointers to each field, the length,
+			/// and the capacity. The caller becomes responsible for the memory, and the only way to free it
+			/// is to convert back into a soa with `from_raw_parts`.
+			///
+			/// Soas created with `with_alignment` are not supported, as `from_raw_parts` could only free them
+			/// with the wrong layout. This is checked in debug builds.
+			pub fn into_raw_parts(self) -> ((*mut $t1 $(, *mut $ts)*), usize, usize) {
+				debug_assert!(self.align == 1, "Cannot decompose a soa created with with_alignment");
+				let parts = (
+					(self.$t1.as_ptr() $(, self.$ts.as_ptr())*),
+					self.len,
+					self.capacity,
+				);
+				forget(self);
+				parts
+			}
+
+			/// Creates a soa directly from the raw components of another soa.
+			///
+			/// # Safety
+			///  * The pointers, length and capacity must have come from `into_raw_parts` on a soa of the same type
+			///  * The soa must not have been reconstituted already, as this would cause a double free
+			///  * The soa must not have been created with `with_alignment`, as the allocation would be freed with the wrong layout
+			pub unsafe fn from_raw_parts(ptrs: (*mut $t1 $(, *mut $ts)*), length: usize, capacity: usize) -> Self {
+				let ($t1 $(, $ts)*) = ptrs;
+				$name {
+					len: length,
+					capacity,
+					$t1: NonNull::new_unchecked($t1),
+					$($ts: NonNull::new_unchecked($ts),)*
+					_marker: (PhantomData $(, PhantomData::<$ts>)*),
+					align: 1,
+					alloc: Global,
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> $name<$t1 $(, $ts)*, A> {
+			/// True if every field is zero-sized, in which case the soa never allocates
+			/// and its capacity is usize::MAX
+			const IS_ZST: bool = size_of::<$t1>() == 0 $(&& size_of::<$ts>() == 0)*;
+
+			/// Creates a new Soa with a capacity of 0 which will allocate from `alloc`.
+			/// If every field is zero-sized the capacity is instead usize::MAX.
+			pub fn new_in(alloc: A) -> Self {
 				$name {
 					len: 0,
-					capacity: 0,
+					capacity: if Self::IS_ZST { usize::MAX } else { 0 },
 					$t1: NonNull::dangling(),
 					$($ts: NonNull::dangling(),)*
 					_marker: (PhantomData $(, PhantomData::<$ts>)*),
+					align: 1,
+					alloc,
+				}
+			}
+
+			/// Creates a new Soa with room for at least `capacity` tuples without reallocating,
+			/// making a single allocation up front from `alloc`.
+			///
+			/// # Panics:
+			///  * If the combined size of the fields exceeds isize::MAX bytes
+			pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+				handle_reserve(Self::try_with_capacity_in(capacity, alloc))
+			}
+
+			/// Tries to create a new Soa with room for at least `capacity` tuples without reallocating,
+			/// making a single allocation up front from `alloc`. Unlike `with_capacity_in`, this returns
+			/// an error instead of panicking or aborting if the capacity overflows or the allocator reports a failure.
+			pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+				let mut soa = Self::new_in(alloc);
+				if capacity != 0 && !Self::IS_ZST {
+					let ($t1 $(, $ts)*) = soa.try_alloc(capacity)?;
+					soa.$t1 = $t1;
+					$(soa.$ts = $ts;)*
+					soa.capacity = capacity;
 				}
+				Ok(soa)
+			}
+
+			/// Creates a new Soa with a capacity of 0 which will allocate from `alloc`,
+			/// where the start of each field's buffer will be aligned to at least align bytes.
+			///
+			/// # Panics:
+			///  * If align is not a power of two
+			pub fn with_alignment_in(align: usize, alloc: A) -> Self {
+				assert!(align.is_power_of_two(), "Alignment must be a power of two");
+				let mut soa = Self::new_in(alloc);
+				soa.align = align;
+				soa.$t1 = Self::dangling(align);
+				$(soa.$ts = Self::dangling(align);)*
+				soa
+			}
+
+			/// Returns the minimum alignment of the start of each field's buffer, as set by `with_alignment`.
+			/// This is 1 by default, meaning each field is aligned only as its type requires.
+			pub fn alignment(&self) -> usize {
+				self.align
+			}
+
+			/// Returns a reference to the underlying allocator.
+			pub fn allocator(&self) -> &A {
+				&self.alloc
+			}
+
+			/// Returns true if the soa owns an allocation, which is never the case when every field is zero-sized
+			fn is_allocated(&self) -> bool {
+				self.capacity != 0 && !Self::IS_ZST
 			}
 
 			fn dealloc(&mut self) {
-				if self.capacity > 0 {
-					let layout = Self::layout_for_capacity(self.capacity).layout;
-					unsafe { Global.dealloc(self.$t1.cast::<u8>(), layout) }
+				if self.is_allocated() {
+					let layout = self.layout_for_capacity(self.capacity).layout;
+					unsafe { self.alloc.deallocate(self.$t1.cast::<u8>(), layout) }
 				}
 			}
 
 			/// Allocates and partitions a new region of uninitialized memory
-			fn alloc(capacity: usize) -> (NonNull<$t1> $(, NonNull<$ts>)*) {
+			fn try_alloc(&self, capacity: usize) -> Result<(NonNull<$t1> $(, NonNull<$ts>)*), TryReserveError> {
+				let layouts = self.try_layout_for_capacity(capacity)?;
 				unsafe {
-					let layouts = Self::layout_for_capacity(capacity);
-					let bytes = Global.alloc(layouts.layout).unwrap();
-					(
+					let bytes = self.alloc.allocate(layouts.layout)
+						.map_err(|_| TryReserveError::AllocError { layout: layouts.layout })?
+						.cast::<u8>();
+					Ok((
 						bytes.cast::<$t1>()
-						$(, NonNull::new_unchecked(bytes.as_ptr().add(layouts.$ts) as *mut $ts))*
-					)
+						$(, Self::field_ptr::<$ts>(bytes, layouts.$ts, self.align))*
+					))
 				}
 			}
 
-			fn check_grow(&mut self) {
+			/// Returns a pointer to the field at offset into the allocation.
+			/// Zero-sized fields take up no space in the allocation, and get a dangling pointer instead.
+			unsafe fn field_ptr<T>(bytes: NonNull<u8>, offset: usize, align: usize) -> NonNull<T> {
+				if size_of::<T>() == 0 {
+					Self::dangling(align)
+				} else {
+					NonNull::new_unchecked(bytes.as_ptr().add(offset) as *mut T)
+				}
+			}
+
+			/// Returns a dangling pointer for a field with no buffer, aligned to both the field's type and align.
+			/// Zero-sized fields only ever have such a pointer, so this is what keeps them aligned as requested.
+			fn dangling<T>(align: usize) -> NonNull<T> {
+				// Both alignments are powers of two, so the address is never null
+				unsafe { NonNull::new_unchecked(without_provenance_mut(align.max(align_of::<T>()))) }
+			}
+
+			/// Moves the initialized tuples into an allocation of exactly `capacity`.
+			/// Requires capacity >= len
+			fn try_reallocate(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+				if self.capacity != 0 && capacity > self.capacity {
+					return unsafe { self.try_grow(capacity) };
+				}
 				unsafe {
-					if self.len == self.capacity {
-						let capacity = (self.capacity * 2).max(4);
-						let ($t1 $(, $ts)*) = Self::alloc(capacity);
+					let ($t1 $(, $ts)*) = self.try_alloc(capacity)?;
 
-						copy_nonoverlapping(self.$t1.as_ptr(), $t1.as_ptr(), self.len);
-						$(
-							copy_nonoverlapping(self.$ts.as_ptr(), $ts.as_ptr(), self.len);
-						)*
+					copy_nonoverlapping(self.$t1.as_ptr(), $t1.as_ptr(), self.len);
+					$(
+						copy_nonoverlapping(self.$ts.as_ptr(), $ts.as_ptr(), self.len);
+					)*
 
-						self.dealloc();
+					self.dealloc();
+
+					// Assign
+					self.$t1 = $t1;
+					$(self.$ts = $ts;)*
+					self.capacity = capacity;
+				}
+				Ok(())
+			}
+
+			/// Grows the allocation to exactly `capacity`, which the allocator may be able to do in place,
+			/// then moves each field to its offset in the larger layout.
+			/// Requires capacity > self.capacity > 0
+			unsafe fn try_grow(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+				let old = self.layout_for_capacity(self.capacity);
+				let new = self.try_layout_for_capacity(capacity)?;
+				let bytes = self.alloc.grow(self.$t1.cast::<u8>(), old.layout, new.layout)
+					.map_err(|_| TryReserveError::AllocError { layout: new.layout })?
+					.cast::<u8>();
+
+				// Each field after the first starts further into the larger layout, so move the fields starting
+				// from the last, to avoid overwriting a field which has not been moved yet.
+				let moves = [$((old.$ts, new.$ts, size_of::<$ts>() * self.len)),*];
+				for &(from, to, size) in moves.iter().rev() {
+					copy(bytes.as_ptr().add(from), bytes.as_ptr().add(to), size);
+				}
+
+				self.$t1 = bytes.cast::<$t1>();
+				$(self.$ts = Self::field_ptr::<$ts>(bytes, new.$ts, self.align);)*
+				self.capacity = capacity;
+				Ok(())
+			}
+
+			fn check_grow(&mut self) {
+				if self.len == self.capacity {
+					self.reserve(1);
+				}
+			}
+
+			/// Reserves capacity for at least `additional` more tuples to be inserted.
+			/// Like vec, the soa may reserve more space to avoid frequent reallocations.
+			///
+			/// # Panics:
+			///  * If the new capacity overflows usize
+			pub fn reserve(&mut self, additional: usize) {
+				handle_reserve(self.try_reserve(additional))
+			}
+
+			/// Reserves the minimum capacity for exactly `additional` more tuples to be inserted.
+			/// Prefer `reserve` if future insertions are expected.
+			///
+			/// # Panics:
+			///  * If the new capacity overflows usize
+			pub fn reserve_exact(&mut self, additional: usize) {
+				handle_reserve(self.try_reserve_exact(additional))
+			}
+
+			/// Tries to reserve capacity for at least `additional` more tuples to be inserted.
+			/// Unlike `reserve`, this returns an error instead of panicking or aborting
+			/// if the capacity overflows or the allocator reports a failure.
+			pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+				if self.capacity - self.len < additional {
+					let required = self.len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+					// If doubling overflows, fall back to the required capacity. The layout check rejects it if too large.
+					let doubled = self.capacity.checked_mul(2).unwrap_or(required);
+					let capacity = required.max(doubled).max(4);
+					self.try_reallocate(capacity)?;
+				}
+				Ok(())
+			}
 
-						// Assign
-						self.$t1 = $t1;
-						$(self.$ts = $ts;)*
-						self.capacity = capacity;
+			/// Tries to reserve the minimum capacity for exactly `additional` more tuples to be inserted.
+			/// Unlike `reserve_exact`, this returns an error instead of panicking or aborting
+			/// if the capacity overflows or the allocator reports a failure.
+			pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+				if self.capacity - self.len < additional {
+					let capacity = self.len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow)?;
+					self.try_reallocate(capacity)?;
+				}
+				Ok(())
+			}
+
+			/// Shrinks the capacity of the soa as much as possible, moving all fields
+			/// into a single allocation sized for exactly `len` tuples.
+			pub fn shrink_to_fit(&mut self) {
+				self.shrink_to(0);
+			}
 
+			/// Shrinks the capacity of the soa with a lower bound.
+			/// The capacity will remain at least as large as both the length and the supplied value.
+			/// If the current capacity is already less than the lower limit, this is a no-op.
+			pub fn shrink_to(&mut self, min_capacity: usize) {
+				let capacity = self.len.max(min_capacity);
+				if self.capacity > capacity && !Self::IS_ZST {
+					if capacity == 0 {
+						self.dealloc();
+						self.$t1 = Self::dangling(self.align);
+						$(self.$ts = Self::dangling(self.align);)*
+						self.capacity = 0;
+					} else {
+						handle_reserve(self.try_reallocate(capacity));
 					}
 				}
 			}
@@ -136,12 +518,148 @@ macro_rules! soa {
 			#[inline(always)]
 			pub fn len(&self) -> usize { self.len }
 
+			/// Returns true if the soa contains no tuples.
+			#[inline(always)]
+			pub fn is_empty(&self) -> bool { self.len == 0 }
+
+			/// Returns the number of tuples the soa can hold without reallocating.
+			#[inline(always)]
+			pub fn capacity(&self) -> usize { self.capacity }
+
+			/// Returns the size in bytes of the single allocation backing all fields of the soa,
+			/// including any padding between fields. This is 0 if nothing has been allocated.
+			pub fn allocated_bytes(&self) -> usize {
+				self.layout().map_or(0, |layout| layout.size())
+			}
+
+			/// Returns the layout of the single allocation backing all fields of the soa,
+			/// or None if nothing has been allocated.
+			pub fn layout(&self) -> Option<Layout> {
+				if !self.is_allocated() {
+					None
+				} else {
+					Some(self.layout_for_capacity(self.capacity).layout)
+				}
+			}
+
+			/// Returns a tuple of raw pointers to the start of each field's buffer.
+			/// The pointers are valid for reads of len elements, and are dangling if nothing has been allocated.
+			pub fn as_ptrs(&self) -> (*const $t1 $(, *const $ts)*) {
+				(self.$t1.as_ptr() as *const $t1 $(, self.$ts.as_ptr() as *const $ts)*)
+			}
+
+			/// Returns a tuple of raw mutable pointers to the start of each field's buffer.
+			/// The pointers are valid for writes of capacity elements, and are dangling if nothing has been allocated.
+			pub fn as_mut_ptrs(&mut self) -> (*mut $t1 $(, *mut $ts)*) {
+				(self.$t1.as_ptr() $(, self.$ts.as_ptr())*)
+			}
+
+			/// Forces the length of the soa to `new_len`.
+			/// This is a low-level operation that maintains none of the normal invariants of the type.
+			/// Usually the length is changed with `push`, `truncate`, etc.
+			///
+			/// # Safety
+			///  * `new_len` must be less than or equal to `capacity()`
+			///  * The elements at `old_len..new_len` must be initialized in every field
+			pub unsafe fn set_len(&mut self, new_len: usize) {
+				debug_assert!(new_len <= self.capacity);
+				self.len = new_len;
+			}
+
+			/// Returns a tuple of the remaining spare capacity of each field as slices of `MaybeUninit`.
+			/// The returned slices can be used to fill the soa with data (eg: by reading from a file)
+			/// before marking the data as initialized using `set_len`.
+			pub fn spare_capacity_mut(&mut self) -> (&mut [MaybeUninit<$t1>] $(, &mut [MaybeUninit<$ts>])*) {
+				let spare = self.capacity - self.len;
+				unsafe {
+					(
+						from_raw_parts_mut(self.$t1.as_ptr().add(self.len) as *mut MaybeUninit<$t1>, spare)
+						$(, from_raw_parts_mut(self.$ts.as_ptr().add(self.len) as *mut MaybeUninit<$ts>, spare))*
+					)
+				}
+			}
+
 			/// Clears the soa, removing all values.
 			/// Note that this method has no effect on the allocated capacity of the soa.
 			pub fn clear(&mut self) {
-				while self.len > 0 {
-					self.pop();
+				self.truncate(0);
+			}
+
+			/// Shortens the soa, keeping the first `len` tuples and dropping the rest.
+			/// If `len` is greater than the current length, this has no effect.
+			/// Note that this method has no effect on the allocated capacity of the soa.
+			pub fn truncate(&mut self, len: usize) {
+				if len >= self.len {
+					return;
+				}
+
+				let remaining = self.len - len;
+				// Set len first so that a panicking drop can't cause a double drop
+				self.len = len;
+				unsafe {
+					// Drop each field as a slice, skipping those without drop glue entirely
+					if needs_drop::<$t1>() {
+						drop_in_place(from_raw_parts_mut(self.$t1.as_ptr().add(len), remaining));
+					}
+					$(
+						if needs_drop::<$ts>() {
+							drop_in_place(from_raw_parts_mut(self.$ts.as_ptr().add(len), remaining));
+						}
+					)*
+				}
+			}
+
+			/// Resizes the soa in place so that len is equal to `new_len`.
+			/// If `new_len` is greater than len, the soa is extended with clones of `value`.
+			/// If `new_len` is less than len, the soa is truncated.
+			pub fn resize(&mut self, new_len: usize, value: ($t1 $(, $ts)*)) where $t1: Clone $(, $ts: Clone)* {
+				if new_len > self.len {
+					self.reserve(new_len - self.len);
+					while self.len < new_len - 1 {
+						self.push(value.clone());
+					}
+					// Move the original into the last position instead of cloning
+					self.push(value);
+				} else {
+					self.truncate(new_len);
+				}
+			}
+
+			/// Resizes the soa in place so that len is equal to `new_len`.
+			/// If `new_len` is greater than len, the soa is extended with tuples generated by calling `f`.
+			/// If `new_len` is less than len, the soa is truncated.
+			pub fn resize_with<F: FnMut() -> ($t1 $(, $ts)*)>(&mut self, new_len: usize, mut f: F) {
+				if new_len > self.len {
+					self.reserve(new_len - self.len);
+					while self.len < new_len {
+						self.push(f());
+					}
+				} else {
+					self.truncate(new_len);
+				}
+			}
+
+			/// Clones the tuples in the range and appends them to the end of the soa.
+			/// Each field is cloned as a separate pass over its slice.
+			///
+			/// # Panics:
+			///  * If the start of the range is greater than the end or the end is greater than len
+			pub fn extend_from_within<R: RangeBounds<usize>>(&mut self, range: R) where $t1: Clone $(, $ts: Clone)* {
+				let (start, end) = resolve_range(range, self.len);
+				let count = end - start;
+				self.reserve(count);
+				unsafe {
+					// If a clone panics the clones already written are leaked, which is safe.
+					for i in 0..count {
+						write(self.$t1.as_ptr().add(self.len + i), (&*self.$t1.as_ptr().add(start + i)).clone());
+					}
+					$(
+						for i in 0..count {
+							write(self.$ts.as_ptr().add(self.len + i), (&*self.$ts.as_ptr().add(start + i)).clone());
+						}
+					)*
 				}
+				self.len += count;
 			}
 
 			/// Appends a tuple to the back of a soa.
@@ -155,6 +673,50 @@ macro_rules! soa {
 				}
 			}
 
+			/// Inserts a tuple at position `index` within the soa, shifting all tuples after it to the right.
+			/// This preserves ordering, but is O(N).
+			///
+			/// # Panics:
+			///  * If index > len
+			pub fn insert(&mut self, index: usize, value: ($t1 $(, $ts)*)) {
+				if index > self.len {
+					panic!("Index out of bounds");
+				}
+
+				self.check_grow();
+				unsafe {
+					let ($t1 $(, $ts)*) = value;
+					let count = self.len - index;
+					{
+						let dst = self.$t1.as_ptr().add(index);
+						copy(dst, dst.add(1), count);
+						write(dst, $t1);
+					}
+					$({
+						let dst = self.$ts.as_ptr().add(index);
+						copy(dst, dst.add(1), count);
+						write(dst, $ts);
+					})*
+				}
+				self.len += 1;
+			}
+
+			/// Appends a tuple to the back of a soa if there is sufficient spare capacity,
+			/// otherwise the tuple is returned as an error. This method never allocates.
+			pub fn push_within_capacity(&mut self, value: ($t1 $(, $ts)*)) -> Result<(), ($t1 $(, $ts)*)> {
+				if self.len == self.capacity {
+					return Err(value);
+				}
+
+				unsafe {
+					let ($t1 $(, $ts)*) = value;
+					write(self.$t1.as_ptr().add(self.len), $t1);
+					$(write(self.$ts.as_ptr().add(self.len), $ts);)*
+					self.len += 1;
+				}
+				Ok(())
+			}
+
 			/// Removes the last tuple from a soa and returns it, or None if it is empty.
 			pub fn pop(&mut self) -> Option<($t1 $(, $ts)*)> {
 				if self.len == 0 {
@@ -170,6 +732,56 @@ macro_rules! soa {
 				}
 			}
 
+			/// Swaps two tuples in the soa, keeping all fields together.
+			///
+			/// # Panics:
+			///  * If either index is out of bounds
+			pub fn swap(&mut self, a: usize, b: usize) {
+				if a >= self.len || b >= self.len {
+					panic!("Index out of bounds");
+				}
+
+				unsafe {
+					swap(self.$t1.as_ptr().add(a), self.$t1.as_ptr().add(b));
+					$(swap(self.$ts.as_ptr().add(a), self.$ts.as_ptr().add(b));)*
+				}
+			}
+
+			/// Reverses the order of the tuples in the soa, in place.
+			pub fn reverse(&mut self) {
+				let ($t1 $(, $ts)*) = self.slices_mut();
+				$t1.reverse();
+				$($ts.reverse();)*
+			}
+
+			/// Rotates the tuples in the soa in place such that the first `mid` tuples move to the end.
+			///
+			/// # Panics:
+			///  * If mid > len
+			pub fn rotate_left(&mut self, mid: usize) {
+				if mid > self.len {
+					panic!("Index out of bounds");
+				}
+
+				let ($t1 $(, $ts)*) = self.slices_mut();
+				$t1.rotate_left(mid);
+				$($ts.rotate_left(mid);)*
+			}
+
+			/// Rotates the tuples in the soa in place such that the last `k` tuples move to the front.
+			///
+			/// # Panics:
+			///  * If k > len
+			pub fn rotate_right(&mut self, k: usize) {
+				if k > self.len {
+					panic!("Index out of bounds");
+				}
+
+				let ($t1 $(, $ts)*) = self.slices_mut();
+				$t1.rotate_right(k);
+				$($ts.rotate_right(k);)*
+			}
+
 			/// Removes a tuple from the soa and returns it.
 			/// The removed tuple is replaced by the last tuple of the soa.
 			/// This does not preserve ordering, but is O(1).
@@ -201,51 +813,393 @@ macro_rules! soa {
 				}
 			}
 
-			fn layout_for_capacity(capacity: usize) -> $L {
-				let layout = Layout::array::<$t1>(capacity).unwrap();
+			/// Removes and returns the tuple at position `index` within the soa,
+			/// shifting all tuples after it to the left.
+			/// This preserves ordering, but is O(N).
+			///
+			/// # Panics:
+			///  * If index is out of bounds
+			pub fn remove(&mut self, index: usize) -> ($t1 $(, $ts)*) {
+				if index >= self.len {
+					panic!("Index out of bounds");
+				}
 
-				$(let (layout, $ts) = layout.extend(Layout::array::<$ts>(capacity).unwrap()).unwrap();)*
+				self.len -= 1;
+				let count = self.len - index;
+				unsafe {
+					let $t1 = self.$t1.as_ptr().add(index);
+					$(let $ts = self.$ts.as_ptr().add(index);)*
 
-				$L {
-					layout
-					$(, $ts)*
+					let v = (
+						read($t1)
+						$(, read($ts))*
+					);
+
+					copy($t1.add(1), $t1, count);
+					$(copy($ts.add(1), $ts, count);)*
+
+					v
 				}
 			}
 
-			/// Returns a tuple of all the destructured tuples added to this soa.
-			#[inline(always)] // Inline for dead code elimination
-			pub fn slices<'a>(&self) -> (&'a [$t1] $(, &'a [$ts])*) {
-				unsafe {
-					(
-						from_raw_parts::<'a>(self.$t1.as_ptr(), self.len),
-						$(from_raw_parts::<'a>(self.$ts.as_ptr(), self.len),)*
-					)
-				}
+			/// Retains only the tuples specified by the predicate, dropping the rest.
+			/// This operates in place in a single pass, keeping all fields together and
+			/// preserving the order of the retained tuples.
+			pub fn retain<F: FnMut((&$t1 $(, &$ts)*)) -> bool>(&mut self, mut f: F) {
+				self.retain_mut(|($t1 $(, $ts)*)| f((&*$t1 $(, &*$ts)*)));
 			}
 
-			/// Returns a tuple of iterators over each field in the soa.
-			#[inline(always)] // Inline for dead code elimination
-			pub fn iters<'a>(&self) -> (Iter<'a, $t1> $(, Iter<'a, $ts>)*) {
-				unsafe {
-					(
-						from_raw_parts::<'a>(self.$t1.as_ptr(), self.len).iter()
-						$(, from_raw_parts::<'a>(self.$ts.as_ptr(), self.len).iter())*
-					)
+			/// Retains only the tuples specified by the predicate, dropping the rest.
+			/// Unlike `retain`, the predicate receives mutable references to each field,
+			/// so tuples can be updated in the same pass that filters them.
+			pub fn retain_mut<F: FnMut((&mut $t1 $(, &mut $ts)*)) -> bool>(&mut self, mut f: F) {
+				let len = self.len;
+				// If f or a drop panics, leak the remaining tuples rather than risk a double drop.
+				self.len = 0;
+
+				let mut kept = 0;
+				for i in 0..len {
+					unsafe {
+						let $t1 = self.$t1.as_ptr().add(i);
+						$(let $ts = self.$ts.as_ptr().add(i);)*
+
+						if f((&mut *$t1 $(, &mut *$ts)*)) {
+							if kept != i {
+								copy_nonoverlapping($t1, self.$t1.as_ptr().add(kept), 1);
+								$(copy_nonoverlapping($ts, self.$ts.as_ptr().add(kept), 1);)*
+							}
+							kept += 1;
+						} else {
+							drop_in_place($t1);
+							$(drop_in_place($ts);)*
+						}
+					}
 				}
+
+				self.len = kept;
 			}
 
-			/// Returns a tuple of mutable iterators over each field in the soa.
-			#[inline(always)] // Inline for dead code elimination
-			pub fn iters_mut<'a>(&mut self) -> (IterMut<'a, $t1> $(, IterMut<'a, $ts>)*) {
+			/// Moves all the tuples of `other` into self, leaving `other` empty.
+			/// The capacity of `other` is left unchanged.
+			pub fn append(&mut self, other: &mut Self) {
+				let count = other.len;
+				self.reserve(count);
 				unsafe {
-					(
-						from_raw_parts_mut::<'a>(self.$t1.as_ptr(), self.len).iter_mut()
-						$(, from_raw_parts_mut::<'a>(self.$ts.as_ptr(), self.len).iter_mut())*
-					)
+					copy_nonoverlapping(other.$t1.as_ptr(), self.$t1.as_ptr().add(self.len), count);
+					$(copy_nonoverlapping(other.$ts.as_ptr(), self.$ts.as_ptr().add(self.len), count);)*
 				}
+				other.len = 0;
+				self.len += count;
 			}
 
-			/// Returns a tuple of all the destructured mutable tuples added to this soa.
+			/// Splits the soa into two at the given index.
+			/// Returns a newly allocated soa containing the tuples in the range [at, len),
+			/// leaving self containing the tuples [0, at) with its previous capacity unchanged.
+			///
+			/// # Panics:
+			///  * If at > len
+			pub fn split_off(&mut self, at: usize) -> Self where A: Clone {
+				if at > self.len {
+					panic!("Index out of bounds");
+				}
+
+				let count = self.len - at;
+				let mut other = Self::with_alignment_in(self.align, self.alloc.clone());
+				other.reserve_exact(count);
+				unsafe {
+					copy_nonoverlapping(self.$t1.as_ptr().add(at), other.$t1.as_ptr(), count);
+					$(copy_nonoverlapping(self.$ts.as_ptr().add(at), other.$ts.as_ptr(), count);)*
+				}
+				self.len = at;
+				other.len = count;
+				other
+			}
+
+			/// Removes consecutive repeated tuples in the soa, in a single pass.
+			/// A tuple is a repeat if all of its fields are equal to those of the previous tuple.
+			pub fn dedup(&mut self) where $t1: PartialEq $(, $ts: PartialEq)* {
+				self.dedup_by(|current, previous| current == previous);
+			}
+
+			/// Removes consecutive tuples in the soa that resolve to the same key, in a single pass.
+			/// This allows deduplicating on a single field, or a value computed from several fields.
+			pub fn dedup_by_key<K: PartialEq, F: FnMut((&mut $t1 $(, &mut $ts)*)) -> K>(&mut self, mut key: F) {
+				self.dedup_by(|current, previous| key(current) == key(previous));
+			}
+
+			/// Removes consecutive tuples in the soa which the closure considers to be repeats, in a single pass.
+			/// Like vec, the closure is passed the current tuple followed by the last retained tuple,
+			/// and if it returns true the current tuple is removed. The first tuple of each run is kept.
+			pub fn dedup_by<F: FnMut((&mut $t1 $(, &mut $ts)*), (&mut $t1 $(, &mut $ts)*)) -> bool>(&mut self, mut same_bucket: F) {
+				if self.len < 2 {
+					return;
+				}
+
+				let len = self.len;
+				// If same_bucket or a drop panics, leak the remaining tuples rather than risk a double drop.
+				self.len = 0;
+
+				let mut kept = 1;
+				for i in 1..len {
+					unsafe {
+						let duplicate = same_bucket(
+							(&mut *self.$t1.as_ptr().add(i) $(, &mut *self.$ts.as_ptr().add(i))*),
+							(&mut *self.$t1.as_ptr().add(kept - 1) $(, &mut *self.$ts.as_ptr().add(kept - 1))*),
+						);
+
+						if duplicate {
+							drop_in_place(self.$t1.as_ptr().add(i));
+							$(drop_in_place(self.$ts.as_ptr().add(i));)*
+						} else {
+							if kept != i {
+								copy_nonoverlapping(self.$t1.as_ptr().add(i), self.$t1.as_ptr().add(kept), 1);
+								$(copy_nonoverlapping(self.$ts.as_ptr().add(i), self.$ts.as_ptr().add(kept), 1);)*
+							}
+							kept += 1;
+						}
+					}
+				}
+
+				self.len = kept;
+			}
+
+			/// Removes the specified range from the soa, returning the removed tuples as an iterator.
+			/// The tail is shifted into place when the iterator is dropped, even if it was not fully consumed.
+			/// If the iterator is leaked (eg: with mem::forget) the soa may lose tuples, but remains safe to use.
+			///
+			/// # Panics:
+			///  * If the start of the range is greater than the end or the end is greater than len
+			pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> $Drain<'_, $t1 $(, $ts)*, A> {
+				let (start, end) = resolve_range(range, self.len);
+				let tail_len = self.len - end;
+				// The drained range and the tail are owned by the drain until it is dropped.
+				self.len = start;
+				$Drain {
+					soa: self,
+					start,
+					end,
+					tail_start: end,
+					tail_len,
+				}
+			}
+
+			/// Replaces the specified range in the soa with the tuples of the given iterator,
+			/// returning the removed tuples as an iterator. The replacement happens when the
+			/// returned iterator is dropped, and the replacement need not be the same length as the range.
+			///
+			/// # Panics:
+			///  * If the start of the range is greater than the end or the end is greater than len
+			pub fn splice<R: RangeBounds<usize>, I: IntoIterator<Item = ($t1 $(, $ts)*)>>(&mut self, range: R, replace_with: I) -> $Splice<'_, $t1 $(, $ts)*, I::IntoIter, A> {
+				$Splice {
+					drain: self.drain(range),
+					replace_with: replace_with.into_iter(),
+				}
+			}
+
+			/// Creates an iterator which uses a closure to determine if a tuple should be removed.
+			/// Matching tuples are removed and yielded as they are found, while the rest are
+			/// compacted in place, preserving order. The closure may mutate the fields of each tuple.
+			/// If the iterator is dropped before being fully consumed, the remaining tuples are retained.
+			pub fn extract_if<F: FnMut((&mut $t1 $(, &mut $ts)*)) -> bool>(&mut self, pred: F) -> $ExtractIf<'_, $t1 $(, $ts)*, F, A> {
+				let old_len = self.len;
+				// The tuples are owned by the iterator until it is dropped.
+				self.len = 0;
+				$ExtractIf {
+					soa: self,
+					index: 0,
+					removed: 0,
+					old_len,
+					pred,
+				}
+			}
+
+			/// Returns the layout for a capacity which has already been allocated, so is known to be valid
+			fn layout_for_capacity(&self, capacity: usize) -> $L {
+				self.try_layout_for_capacity(capacity).expect("The layout of an existing allocation is valid")
+			}
+
+			/// Computes the combined layout of all fields for capacity, with the offset of each field after the first.
+			/// Each field starts on a multiple of the larger of its own alignment and `self.align`.
+			/// Layout::array and Layout::extend fail if the size would exceed isize::MAX bytes,
+			/// which is reported as a capacity overflow, so no allocation ever exceeds isize::MAX bytes.
+			fn try_layout_for_capacity(&self, capacity: usize) -> Result<$L, TryReserveError> {
+				let layout = Layout::array::<$t1>(capacity)?.align_to(self.align)?;
+
+				// Zero-sized fields are left out of the layout, so they add no padding
+				$(let (layout, $ts) = if size_of::<$ts>() == 0 {
+					(layout, 0)
+				} else {
+					layout.extend(Layout::array::<$ts>(capacity)?.align_to(self.align)?)?
+				};)*
+
+				Ok($L {
+					layout
+					$(, $ts)*
+				})
+			}
+
+			/// Consumes the soa and leaks its allocation, returning a tuple of mutable slices over each field.
+			/// The lifetime may be chosen freely, including 'static. This is useful for data which
+			/// lives for the remainder of the program. Dropping the slices will not free the memory.
+			pub fn leak<'a>(self) -> (&'a mut [$t1] $(, &'a mut [$ts])*) where A: 'a {
+				let slices = unsafe {
+					(
+						from_raw_parts_mut(self.$t1.as_ptr(), self.len)
+						$(, from_raw_parts_mut(self.$ts.as_ptr(), self.len))*
+					)
+				};
+				forget(self);
+				slices
+			}
+
+			/// Returns a tuple of all the destructured tuples added to this soa.
+			#[inline(always)] // Inline for dead code elimination
+			pub fn slices<'a>(&self) -> (&'a [$t1] $(, &'a [$ts])*) {
+				unsafe {
+					(
+						from_raw_parts::<'a>(self.$t1.as_ptr(), self.len),
+						$(from_raw_parts::<'a>(self.$ts.as_ptr(), self.len),)*
+					)
+				}
+			}
+
+			/// Returns an iterator over the tuples of the soa, with a reference to each field.
+			pub fn iter(&self) -> $Iter<'_, $t1 $(, $ts)*, A> {
+				$Iter {
+					soa: self,
+					start: 0,
+					end: self.len,
+				}
+			}
+
+			/// Returns an iterator over copies of the tuples of the soa.
+			pub fn iter_copied(&self) -> Map<$Iter<'_, $t1 $(, $ts)*, A>, fn((&$t1 $(, &$ts)*)) -> ($t1 $(, $ts)*)> where $t1: Copy $(, $ts: Copy)* {
+				self.iter().map(|($t1 $(, $ts)*)| (*$t1 $(, *$ts)*))
+			}
+
+			/// Returns an iterator over clones of the tuples of the soa.
+			pub fn iter_cloned(&self) -> Map<$Iter<'_, $t1 $(, $ts)*, A>, fn((&$t1 $(, &$ts)*)) -> ($t1 $(, $ts)*)> where $t1: Clone $(, $ts: Clone)* {
+				self.iter().map(|($t1 $(, $ts)*)| ($t1.clone() $(, $ts.clone())*))
+			}
+
+			/// Returns an iterator over the tuples of the soa, with a mutable reference to each field.
+			pub fn iter_mut(&mut self) -> $IterMut<'_, $t1 $(, $ts)*, A> {
+				let end = self.len;
+				$IterMut {
+					soa: self,
+					start: 0,
+					end,
+				}
+			}
+
+			/// Returns an iterator over chunks of size tuples at a time, starting at the beginning of the soa.
+			/// Each chunk is a tuple of slices with one slice per field. The last chunk is shorter if size does not divide the length.
+			///
+			/// # Panics:
+			///  * If size is 0
+			pub fn chunks(&self, size: usize) -> $Chunks<'_, $t1 $(, $ts)*, A> {
+				assert!(size != 0, "Chunk size must be non-zero");
+				$Chunks {
+					soa: self,
+					start: 0,
+					end: self.len,
+					size,
+				}
+			}
+
+			/// Returns an iterator over chunks of exactly size tuples at a time, starting at the beginning of the soa.
+			/// If size does not divide the length, the last up to size - 1 tuples are omitted and can be retrieved from the `remainder` method of the iterator.
+			///
+			/// # Panics:
+			///  * If size is 0
+			pub fn chunks_exact(&self, size: usize) -> $ChunksExact<'_, $t1 $(, $ts)*, A> {
+				assert!(size != 0, "Chunk size must be non-zero");
+				let end = self.len - self.len % size;
+				$ChunksExact {
+					soa: self,
+					start: 0,
+					end,
+					size,
+				}
+			}
+
+			/// Returns an iterator over chunks of size tuples at a time, starting at the end of the soa.
+			/// The last chunk is shorter if size does not divide the length.
+			///
+			/// # Panics:
+			///  * If size is 0
+			pub fn rchunks(&self, size: usize) -> $RChunks<'_, $t1 $(, $ts)*, A> {
+				assert!(size != 0, "Chunk size must be non-zero");
+				$RChunks {
+					soa: self,
+					start: 0,
+					end: self.len,
+					size,
+				}
+			}
+
+			/// Returns an iterator over all contiguous windows of size tuples, as tuples of slices.
+			/// The windows overlap. If the soa is shorter than size, the iterator returns no values.
+			///
+			/// # Panics:
+			///  * If size is 0
+			pub fn windows(&self, size: usize) -> $Windows<'_, $t1 $(, $ts)*, A> {
+				assert!(size != 0, "Window size must be non-zero");
+				$Windows {
+					soa: self,
+					start: 0,
+					end: (self.len + 1).saturating_sub(size),
+					size,
+				}
+			}
+
+			/// Returns an iterator over runs of tuples, as tuples of slices, where pred returns true for each pair of adjacent tuples in the run.
+			/// pred is called with the previous tuple and then the next tuple, eg: `soa.chunk_by(|(a, ..), (b, ..)| a == b)`
+			pub fn chunk_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*)) -> bool>(&self, pred: F) -> $ChunkBy<'_, $t1 $(, $ts)*, F, A> {
+				$ChunkBy {
+					soa: self,
+					start: 0,
+					end: self.len,
+					pred,
+				}
+			}
+
+			/// Returns slices of len tuples beginning at start, with one slice per field.
+			///
+			/// # Safety
+			/// start + len must not be greater than the length of the soa
+			#[inline(always)]
+			unsafe fn slices_at(&self, start: usize, len: usize) -> (&[$t1] $(, &[$ts])*) {
+				(
+					from_raw_parts(self.$t1.as_ptr().add(start), len)
+					$(, from_raw_parts(self.$ts.as_ptr().add(start), len))*
+				)
+			}
+
+			/// Returns a tuple of iterators over each field in the soa.
+			#[inline(always)] // Inline for dead code elimination
+			pub fn iters(&self) -> (Iter<'_, $t1> $(, Iter<'_, $ts>)*) {
+				unsafe {
+					(
+						from_raw_parts(self.$t1.as_ptr(), self.len).iter()
+						$(, from_raw_parts(self.$ts.as_ptr(), self.len).iter())*
+					)
+				}
+			}
+
+			/// Returns a tuple of mutable iterators over each field in the soa.
+			/// The iterators borrow the soa mutably, so one field can be walked while another is updated.
+			#[inline(always)] // Inline for dead code elimination
+			pub fn iters_mut(&mut self) -> (IterMut<'_, $t1> $(, IterMut<'_, $ts>)*) {
+				unsafe {
+					(
+						from_raw_parts_mut(self.$t1.as_ptr(), self.len).iter_mut()
+						$(, from_raw_parts_mut(self.$ts.as_ptr(), self.len).iter_mut())*
+					)
+				}
+			}
+
+			/// Returns a tuple of all the destructured mutable tuples added to this soa.
 			#[inline(always)] // Inline for dead code elimination
 			pub fn slices_mut<'a>(&self) -> (&'a mut [$t1] $(, &'a mut [$ts])*) {
 				unsafe {
@@ -256,196 +1210,4028 @@ macro_rules! soa {
 				}
 			}
 
-			/// This is analogous to the index operator in vec, but returns a tuple of references.
-			/// ## Panics
-			/// * If index is >= len
-			pub fn get<'a>(&self, index: usize) -> (&'a $t1 $(, &'a $ts)*) {
-				unsafe {
-					if index >= self.len {
-						panic!("Index out of range");
+			/// Returns the field at index I as bytes, eg: `soa.column_bytes::<0>()`
+			#[cfg(feature = "bytemuck")]
+			pub fn column_bytes<const I: usize>(&self) -> &[u8] where Self: Column<I>, <Self as Column<I>>::Item: bytemuck::Pod {
+				bytemuck::cast_slice(Column::<I>::column(self))
+			}
+
+			/// Returns the field at index I as mutable bytes, eg: `soa.column_bytes_mut::<0>()`
+			#[cfg(feature = "bytemuck")]
+			pub fn column_bytes_mut<const I: usize>(&mut self) -> &mut [u8] where Self: Column<I>, <Self as Column<I>>::Item: bytemuck::Pod {
+				bytemuck::cast_slice_mut(Column::<I>::column_mut(self))
+			}
+
+			/// Returns a tuple of references to the fields at index, or None if index is out of bounds.
+			pub fn get(&self, index: usize) -> Option<(&$t1 $(, &$ts)*)> {
+				if index >= self.len {
+					None
+				} else {
+					unsafe { Some(self.get_unchecked(index)) }
+				}
+			}
+
+			/// Returns a tuple of references to the fields at index, without doing bounds checking.
+			///
+			/// # Safety
+			///  * index must be < len
+			pub unsafe fn get_unchecked(&self, index: usize) -> (&$t1 $(, &$ts)*) {
+				debug_assert!(index < self.len);
+				(
+					&*self.$t1.as_ptr().add(index)
+					$(, &*self.$ts.as_ptr().add(index))*
+				)
+			}
+
+			/// This is analogous to the index operator in vec, but returns a proxy holding a reference to each field.
+			///
+			/// # Panics:
+			///  * If index is out of bounds
+			pub fn row(&self, index: usize) -> $Ref<'_, $t1 $(, $ts)*> {
+				match self.get(index) {
+					Some(($t1 $(, $ts)*)) => $Ref($t1 $(, $ts)*),
+					None => panic!("Index out of bounds"),
+				}
+			}
+
+			/// This is analogous to the mutable index operator in vec, but returns a proxy holding a mutable reference to each field.
+			///
+			/// # Panics:
+			///  * If index is out of bounds
+			pub fn row_mut(&mut self, index: usize) -> $RefMut<'_, $t1 $(, $ts)*> {
+				match self.get_mut(index) {
+					Some(($t1 $(, $ts)*)) => $RefMut($t1 $(, $ts)*),
+					None => panic!("Index out of bounds"),
+				}
+			}
+
+			/// Returns a tuple of mutable references to the fields at index, or None if index is out of bounds.
+			pub fn get_mut(&mut self, index: usize) -> Option<(&mut $t1 $(, &mut $ts)*)> {
+				if index >= self.len {
+					None
+				} else {
+					unsafe { Some(self.get_unchecked_mut(index)) }
+				}
+			}
+
+			/// Returns a tuple of mutable references to the fields at index, without doing bounds checking.
+			///
+			/// # Safety
+			///  * index must be < len
+			pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> (&mut $t1 $(, &mut $ts)*) {
+				debug_assert!(index < self.len);
+				(
+					&mut *self.$t1.as_ptr().add(index)
+					$(, &mut *self.$ts.as_ptr().add(index))*
+				)
+			}
+
+			/// Returns tuples of mutable references to the fields at several distinct indices at once.
+			/// Returns None if any index is out of bounds, or if the same index was passed more than once.
+			pub fn get_many_mut<const N: usize>(&mut self, indices: [usize; N]) -> Option<[(&mut $t1 $(, &mut $ts)*); N]> {
+				for (i, &index) in indices.iter().enumerate() {
+					if index >= self.len || indices[..i].contains(&index) {
+						return None;
+					}
+				}
+
+				let $t1 = self.$t1.as_ptr();
+				$(let $ts = self.$ts.as_ptr();)*
+				// The indices are in bounds and disjoint, so none of the references alias
+				Some(indices.map(|index| unsafe {
+					(&mut *$t1.add(index) $(, &mut *$ts.add(index))*)
+				}))
+			}
+
+			/// Returns a tuple of references to the first tuple in the soa, or None if it is empty.
+			pub fn first(&self) -> Option<(&$t1 $(, &$ts)*)> {
+				self.get(0)
+			}
+
+			/// Returns a tuple of mutable references to the first tuple in the soa, or None if it is empty.
+			pub fn first_mut(&mut self) -> Option<(&mut $t1 $(, &mut $ts)*)> {
+				self.get_mut(0)
+			}
+
+			/// Returns a tuple of references to the last tuple in the soa, or None if it is empty.
+			pub fn last(&self) -> Option<(&$t1 $(, &$ts)*)> {
+				if self.len == 0 {
+					None
+				} else {
+					self.get(self.len - 1)
+				}
+			}
+
+			/// Returns a tuple of mutable references to the last tuple in the soa, or None if it is empty.
+			pub fn last_mut(&mut self) -> Option<(&mut $t1 $(, &mut $ts)*)> {
+				if self.len == 0 {
+					None
+				} else {
+					let index = self.len - 1;
+					self.get_mut(index)
+				}
+			}
+
+			/// Consumes the soa, moving the tuples for which pred returns true into the first soa and the rest into the second.
+			/// The order of the tuples is preserved in both.
+			pub fn partition<P: FnMut((&$t1 $(, &$ts)*)) -> bool>(self, mut pred: P) -> (Self, Self) where A: Clone {
+				let mut matched = Self::with_alignment_in(self.align, self.alloc.clone());
+				let mut unmatched = Self::with_alignment_in(self.align, self.alloc.clone());
+				for tuple in self {
+					let matches = {
+						let ($t1 $(, $ts)*) = &tuple;
+						pred(($t1 $(, $ts)*))
+					};
+					if matches {
+						matched.push(tuple);
+					} else {
+						unmatched.push(tuple);
+					}
+				}
+				(matched, unmatched)
+			}
+
+			/// Consumes two soas which are sorted according to f and merges them into one sorted soa with a single allocation.
+			/// The merge is stable, so for equal tuples those from self come before those from other.
+			pub fn merge_sorted<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(self, other: Self, mut f: F) -> Self where A: Clone {
+				let capacity = handle_reserve(self.len.checked_add(other.len).ok_or(TryReserveError::CapacityOverflow));
+				let mut merged = Self::with_alignment_in(self.align, self.alloc.clone());
+				merged.reserve_exact(capacity);
+				let mut lh = self.into_iter().peekable();
+				let mut rh = other.into_iter().peekable();
+				loop {
+					let take_rh = match (lh.peek(), rh.peek()) {
+						(Some(l), Some(r)) => {
+							let l = { let ($t1 $(, $ts)*) = l; ($t1 $(, $ts)*) };
+							let r = { let ($t1 $(, $ts)*) = r; ($t1 $(, $ts)*) };
+							f(r, l) == Ordering::Less
+						},
+						(Some(_), None) => false,
+						(None, Some(_)) => true,
+						(None, None) => break,
+					};
+					let next = if take_rh { rh.next() } else { lh.next() };
+					merged.push(next.unwrap());
+				}
+				merged
+			}
+
+			/// Returns true if the soa contains a tuple equal to value.
+			pub fn contains(&self, value: &($t1 $(, $ts)*)) -> bool where $t1: PartialEq $(, $ts: PartialEq)* {
+				let ($t1 $(, $ts)*) = value;
+				self.iter().any(|tuple| tuple == ($t1 $(, $ts)*))
+			}
+
+			/// Returns the index of the first tuple for which pred returns true, or None if there is no such tuple.
+			pub fn position<P: FnMut((&$t1 $(, &$ts)*)) -> bool>(&self, pred: P) -> Option<usize> {
+				self.iter().position(pred)
+			}
+
+			/// Returns the index of the minimum tuple according to f, or None if the soa is empty.
+			/// If several tuples are equally minimum, the first index is returned.
+			pub fn position_min_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&self, mut f: F) -> Option<usize> {
+				(0..self.len).min_by(|a, b| unsafe { f(self.get_unchecked(*a), self.get_unchecked(*b)) })
+			}
+
+			/// Returns the index of the maximum tuple according to f, or None if the soa is empty.
+			/// If several tuples are equally maximum, the last index is returned.
+			pub fn position_max_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&self, mut f: F) -> Option<usize> {
+				(0..self.len).max_by(|a, b| unsafe { f(self.get_unchecked(*a), self.get_unchecked(*b)) })
+			}
+
+			/// Returns a tuple of references to the fields of the minimum tuple according to f, or None if the soa is empty.
+			pub fn min_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&self, f: F) -> Option<(&$t1 $(, &$ts)*)> {
+				self.position_min_by(f).map(|index| unsafe { self.get_unchecked(index) })
+			}
+
+			/// Returns a tuple of references to the fields of the maximum tuple according to f, or None if the soa is empty.
+			pub fn max_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&self, f: F) -> Option<(&$t1 $(, &$ts)*)> {
+				self.position_max_by(f).map(|index| unsafe { self.get_unchecked(index) })
+			}
+
+			/// Sorts the soa keeping related data together.
+			pub fn sort_unstable_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&mut self, f: F) {
+				self.sort_unstable_by_with(&mut SortScratch::new(), f);
+			}
+
+			/// Sorts the soa keeping related data together, using the buffers of scratch instead of allocating new ones.
+			/// Reusing the same scratch for repeated sorts avoids allocating once the buffers are large enough.
+			pub fn sort_unstable_by_with<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&mut self, scratch: &mut SortScratch, mut f: F) {
+				if self.len < 2 {
+					return;
+				}
+				let indices = &mut scratch.indices;
+				indices.clear();
+				indices.extend(0..self.len);
+
+				indices.sort_unstable_by(|a, b| unsafe {
+					f(
+						(&*self.$t1.as_ptr().add(*a) $(, &*self.$ts.as_ptr().add(*a))*, ),
+						(&*self.$t1.as_ptr().add(*b) $(, &*self.$ts.as_ptr().add(*b))*, ),
+					)});
+
+				self.apply_sorted_indices(indices, &mut scratch.lookup);
+			}
+
+			/// Reorders the soa so that the tuple at index is at its sorted position, keeping related data together.
+			/// Tuples before index are less than or equal to it and tuples after index are greater than or equal to it, in an unspecified order.
+			/// Returns mutable references to the fields of the tuple at index.
+			///
+			/// # Panics:
+			///  * If index is out of bounds
+			pub fn select_nth_unstable_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&mut self, index: usize, mut f: F) -> (&mut $t1 $(, &mut $ts)*) {
+				assert!(index < self.len, "Index out of bounds");
+				let mut indices: Vec<usize> = (0..self.len).collect();
+
+				indices.select_nth_unstable_by(index, |a, b| unsafe { f(self.get_unchecked(*a), self.get_unchecked(*b)) });
+
+				self.apply_sorted_indices(&mut indices, &mut Vec::new());
+				unsafe { self.get_unchecked_mut(index) }
+			}
+
+			/// Binary searches a sorted soa with a comparator function, which returns the order of a tuple relative to the target.
+			/// Returns Ok with the index of a matching tuple, or Err with the index where a matching tuple could be inserted to keep the soa sorted.
+			/// If there are multiple matches any one of them may be returned.
+			pub fn binary_search_by<F: FnMut((&$t1 $(, &$ts)*)) -> Ordering>(&self, mut f: F) -> Result<usize, usize> {
+				let mut left = 0;
+				let mut right = self.len;
+				while left < right {
+					let mid = left + (right - left) / 2;
+					match f(unsafe { self.get_unchecked(mid) }) {
+						Ordering::Less => left = mid + 1,
+						Ordering::Greater => right = mid,
+						Ordering::Equal => return Ok(mid),
+					}
+				}
+				Err(left)
+			}
+
+			/// Binary searches a soa which is sorted by the key extracted from each tuple. See `binary_search_by` for more information.
+			pub fn binary_search_by_key<K: Ord, F: FnMut((&$t1 $(, &$ts)*)) -> K>(&self, key: &K, mut f: F) -> Result<usize, usize> {
+				self.binary_search_by(|tuple| f(tuple).cmp(key))
+			}
+
+			/// Returns the index of the first tuple for which pred returns false, given that the soa is partitioned so that
+			/// pred returns true for all tuples before some index and false for all tuples after it.
+			/// eg: the first tuple with a key >= k is at `soa.partition_point(|(key, ..)| *key < k)`
+			pub fn partition_point<P: FnMut((&$t1 $(, &$ts)*)) -> bool>(&self, mut pred: P) -> usize {
+				self.binary_search_by(|tuple| if pred(tuple) { Ordering::Less } else { Ordering::Greater }).unwrap_or_else(|index| index)
+			}
+
+			/// Returns the range of indices of the tuples whose key equals key, in a soa which is sorted by the key extracted from each tuple.
+			/// If there are no such tuples, the range is empty and starts where a tuple with the key could be inserted.
+			pub fn equal_range_by_key<K: Ord, F: FnMut((&$t1 $(, &$ts)*)) -> K>(&self, key: &K, mut f: F) -> Range<usize> {
+				let start = self.partition_point(|tuple| f(tuple) < *key);
+				let end = self.partition_point(|tuple| f(tuple) <= *key);
+				start..end
+			}
+
+			/// Inserts a tuple into a sorted soa at the position which keeps it sorted, and returns that index.
+			/// The tuple is inserted after any tuples which compare equal to it.
+			pub fn insert_sorted_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&mut self, tuple: ($t1 $(, $ts)*), mut f: F) -> usize {
+				let index = {
+					let ($t1 $(, $ts)*) = &tuple;
+					self.partition_point(|other| f(other, ($t1 $(, $ts)*)) != Ordering::Greater)
+				};
+				self.insert(index, tuple);
+				index
+			}
+
+			/// Returns true if the tuples are sorted lexicographically.
+			pub fn is_sorted(&self) -> bool where $t1: PartialOrd $(, $ts: PartialOrd)* {
+				self.is_sorted_by(|a, b| a <= b)
+			}
+
+			/// Returns true if f returns true for each pair of adjacent tuples, where f checks that its arguments are in order.
+			pub fn is_sorted_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*)) -> bool>(&self, mut f: F) -> bool {
+				(1..self.len).all(|i| unsafe { f(self.get_unchecked(i - 1), self.get_unchecked(i)) })
+			}
+
+			/// Returns the indices of the tuples in sorted order, without reordering the soa.
+			/// The tuple which would be sorted to position i is at index `indices[i]`.
+			pub fn arg_sort_unstable_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&self, mut f: F) -> Vec<usize> {
+				let mut indices: Vec<usize> = (0..self.len).collect();
+				indices.sort_unstable_by(|a, b| unsafe { f(self.get_unchecked(*a), self.get_unchecked(*b)) });
+				indices
+			}
+
+			/// Sorts the soa by the key extracted from each tuple, keeping related data together.
+			pub fn sort_unstable_by_key<K: Ord, F: FnMut((&$t1 $(, &$ts)*)) -> K>(&mut self, mut f: F) {
+				self.sort_unstable_by(|a, b| f(a).cmp(&f(b)));
+			}
+
+			/// Sorts the soa by the key extracted from each tuple, keeping related data together.
+			/// The key function is called only once per tuple, so this is faster than `sort_by_key` for expensive keys.
+			/// This sort is stable.
+			pub fn sort_by_cached_key<K: Ord, F: FnMut((&$t1 $(, &$ts)*)) -> K>(&mut self, mut f: F) {
+				if self.len < 2 {
+					return;
+				}
+				let mut keys: Vec<(K, usize)> = self.iter().enumerate().map(|(i, tuple)| (f(tuple), i)).collect();
+
+				// The index breaks ties, so the unstable sort gives a stable order
+				keys.sort_unstable();
+
+				let mut indices: Vec<usize> = keys.into_iter().map(|(_, index)| index).collect();
+				self.apply_sorted_indices(&mut indices, &mut Vec::new());
+			}
+
+			/// Sorts the soa by the integer key extracted from each tuple with a radix sort, keeping related data together.
+			/// This runs in O(n) time for a fixed size of key, and calls the key function only once per tuple.
+			pub fn sort_unstable_by_key_radix<K: RadixKey, F: FnMut((&$t1 $(, &$ts)*)) -> K>(&mut self, mut f: F) {
+				if self.len < 2 {
+					return;
+				}
+				let keys: Vec<u64> = self.iter().map(|tuple| f(tuple).to_radix_bits()).collect();
+				let mut indices: Vec<usize> = (0..self.len).collect();
+				let mut scratch = vec![0usize; self.len];
+
+				// Least significant digit first, where each pass is a stable counting sort on one byte
+				for byte in 0..K::BYTES {
+					let shift = byte * 8;
+					let mut counts = [0usize; 256];
+					for key in &keys {
+						counts[(key >> shift) as usize & 0xFF] += 1;
+					}
+					// All of the keys have the same byte, so this pass would not move anything
+					if counts.contains(&self.len) {
+						continue;
 					}
 
-					(
-						&*self.$t1.as_ptr().add(index)
-						$(, &*self.$ts.as_ptr().add(index))*
-					)
-				}
-			}
+					let mut offset = 0;
+					for count in counts.iter_mut() {
+						let next = offset + *count;
+						*count = offset;
+						offset = next;
+					}
+					for index in &indices {
+						let digit = (keys[*index] >> shift) as usize & 0xFF;
+						scratch[counts[digit]] = *index;
+						counts[digit] += 1;
+					}
+					std::mem::swap(&mut indices, &mut scratch);
+				}
+
+				self.apply_sorted_indices(&mut indices, &mut Vec::new());
+			}
+
+			/// Sorts the soa lexicographically by the tuples, keeping related data together.
+			pub fn sort_unstable(&mut self) where $t1: Ord $(, $ts: Ord)* {
+				self.sort_unstable_by(|a, b| a.cmp(&b));
+			}
+
+			/// Sorts the soa keeping related data together. This sort is stable, so equal tuples keep their order.
+			pub fn sort_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&mut self, mut f: F) {
+				if self.len < 2 {
+					return;
+				}
+				let mut indices: Vec<usize> = (0..self.len).collect();
+
+				indices.sort_by(|a, b| unsafe { f(self.get_unchecked(*a), self.get_unchecked(*b)) });
+
+				self.apply_sorted_indices(&mut indices, &mut Vec::new());
+			}
+
+			/// Sorts the soa by the key extracted from each tuple, keeping related data together. This sort is stable.
+			pub fn sort_by_key<K: Ord, F: FnMut((&$t1 $(, &$ts)*)) -> K>(&mut self, mut f: F) {
+				self.sort_by(|a, b| f(a).cmp(&f(b)));
+			}
+
+			/// Sorts the soa lexicographically by the tuples, keeping related data together. This sort is stable.
+			pub fn sort(&mut self) where $t1: Ord $(, $ts: Ord)* {
+				self.sort_by(|a, b| a.cmp(&b));
+			}
+
+			/// Reorders the soa so that the tuple at index `indices[i]` moves to i, for all fields at once.
+			/// This applies a permutation such as the one returned by `arg_sort_unstable_by`.
+			///
+			/// # Panics:
+			///  * If the length of indices is not the length of the soa
+			///  * In debug builds, if indices is not a permutation of `0..len`.
+			///    In release builds an index out of bounds still panics, but the order of the soa is unspecified if there are duplicates.
+			pub fn apply_permutation(&mut self, indices: &[usize]) {
+				assert_eq!(indices.len(), self.len, "The permutation must have one index per tuple");
+				if cfg!(debug_assertions) {
+					let mut seen = vec![false; self.len];
+					for &index in indices {
+						assert!(index < self.len && !seen[index], "The indices are not a permutation");
+						seen[index] = true;
+					}
+				}
+				let mut indices = indices.to_vec();
+				self.apply_sorted_indices(&mut indices, &mut Vec::new());
+			}
+
+			/// Moves each tuple to its sorted position, where indices[i] is the current index of the tuple which belongs at i.
+			/// indices is used as scratch space and is left in an unspecified order, and lookup is a buffer for the inverse of indices.
+			fn apply_sorted_indices(&mut self, indices: &mut [usize], lookup: &mut Vec<usize>) {
+				// Example
+				// c b d e a
+				// 4 1 0 2 3 // indices
+				// 2 1 3 4 0 // lookup
+
+				// Zeroed rather than uninitialized, so that indices which are not a permutation can't read uninitialized memory
+				lookup.clear();
+				lookup.resize(self.len, 0);
+				for (i, index) in indices.iter().enumerate() {
+					lookup[*index] = i;
+				}
+
+				let ($t1 $(, $ts)*) = self.slices_mut();
+
+				for i in 0..indices.len() {
+					let dest = indices[i]; // The index that should go here
+					if i != dest {
+						// Swap
+						$t1.swap(i, dest);
+						$($ts.swap(i, dest);)*
+
+						// Account for swaps that already happened
+						indices[lookup[i]] = dest;
+						lookup[dest] = lookup[i];
+					}
+				}
+			}
+		}
+
+		struct $L {
+			layout: Layout,
+			$($ts: usize,)*
+		}
+
+
+		/// A proxy for a single tuple of a soa, holding a reference to each field.
+		/// The fields are accessed positionally, eg: `soa.row(i).0`
+		#[derive(Debug)]
+		pub struct $Ref<'a, $t1 $(, $ts)*>(pub &'a $t1 $(, pub &'a $ts)*);
+
+		impl<'a, $t1 $(, $ts)*> Clone for $Ref<'a, $t1 $(, $ts)*> {
+			fn clone(&self) -> Self { *self }
+		}
+
+		impl<'a, $t1 $(, $ts)*> Copy for $Ref<'a, $t1 $(, $ts)*> {}
+
+		/// A proxy for a single tuple of a soa, holding a mutable reference to each field.
+		/// The fields are accessed positionally, eg: `*soa.row_mut(i).0 = value`
+		#[derive(Debug)]
+		pub struct $RefMut<'a, $t1 $(, $ts)*>(pub &'a mut $t1 $(, pub &'a mut $ts)*);
+
+		impl<$t1: Sized $(, $ts: Sized)*> FromIterator<($t1 $(, $ts)*)> for $name<$t1 $(, $ts)*> {
+			fn from_iter<I: IntoIterator<Item = ($t1 $(, $ts)*)>>(iter: I) -> Self {
+				let mut soa = Self::new();
+				soa.extend(iter);
+				soa
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> Extend<($t1 $(, $ts)*)> for $name<$t1 $(, $ts)*, A> {
+			/// Appends the tuples of the iterator, reserving space up front for the lower bound of its size hint.
+			fn extend<I: IntoIterator<Item = ($t1 $(, $ts)*)>>(&mut self, iter: I) {
+				let iter = iter.into_iter();
+				self.reserve(iter.size_hint().0);
+				for value in iter {
+					self.push(value);
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*> From<Vec<($t1 $(, $ts)*)>> for $name<$t1 $(, $ts)*> {
+			/// Moves the tuples out of the vec, splitting them into fields in a single pass
+			/// over one exactly sized allocation.
+			fn from(vec: Vec<($t1 $(, $ts)*)>) -> Self {
+				let mut soa = Self::with_capacity(vec.len());
+				soa.extend(vec);
+				soa
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> From<[($t1 $(, $ts)*); N]> for $name<$t1 $(, $ts)*> {
+			/// Moves the tuples out of the array, splitting them into fields in a single pass
+			/// over one exactly sized allocation.
+			fn from(array: [($t1 $(, $ts)*); N]) -> Self {
+				let mut soa = Self::with_capacity(N);
+				soa.extend(array);
+				soa
+			}
+		}
+
+		impl<'a, $t1: Clone $(, $ts: Clone)*> TryFrom<(&'a [$t1] $(, &'a [$ts])*)> for $name<$t1 $(, $ts)*> {
+			type Error = LengthMismatchError;
+
+			/// See `try_from_slices`
+			fn try_from(slices: (&'a [$t1] $(, &'a [$ts])*)) -> Result<Self, Self::Error> {
+				Self::try_from_slices(slices)
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> From<$name<$t1 $(, $ts)*, A>> for Vec<($t1 $(, $ts)*)> {
+			/// Moves the fields out of the soa, interleaving them into tuples of an exactly sized vec.
+			fn from(soa: $name<$t1 $(, $ts)*, A>) -> Self {
+				let mut vec = Vec::with_capacity(soa.len);
+				vec.extend(soa);
+				vec
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> IntoIterator for $name<$t1 $(, $ts)*, A> {
+			type Item = ($t1 $(, $ts)*);
+			type IntoIter = $IntoIter<$t1 $(, $ts)*, A>;
+
+			/// Creates a consuming iterator which moves each tuple out of the soa, front to back.
+			fn into_iter(mut self) -> Self::IntoIter {
+				let end = self.len;
+				// The tuples are owned by the iterator, which leaves only the allocation for the soa to free.
+				self.len = 0;
+				$IntoIter {
+					soa: self,
+					start: 0,
+					end,
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> IntoIterator for &'a $name<$t1 $(, $ts)*, A> {
+			type Item = (&'a $t1 $(, &'a $ts)*);
+			type IntoIter = $Iter<'a, $t1 $(, $ts)*, A>;
+
+			fn into_iter(self) -> Self::IntoIter {
+				self.iter()
+			}
+		}
+
+		/// An iterator over the tuples of a soa. See the `iter` method for more information.
+		pub struct $Iter<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a $name<$t1 $(, $ts)*, A>,
+			/// Index of the next tuple to be yielded from the front
+			start: usize,
+			/// Index one past the next tuple to be yielded from the back
+			end: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Clone for $Iter<'a, $t1 $(, $ts)*, A> {
+			fn clone(&self) -> Self {
+				$Iter {
+					soa: self.soa,
+					start: self.start,
+					end: self.end,
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $Iter<'a, $t1 $(, $ts)*, A> {
+			type Item = (&'a $t1 $(, &'a $ts)*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					unsafe { Some(self.soa.get_unchecked(index)) }
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $Iter<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					unsafe { Some(self.soa.get_unchecked(self.end)) }
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $Iter<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $Iter<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> IntoIterator for &'a mut $name<$t1 $(, $ts)*, A> {
+			type Item = (&'a mut $t1 $(, &'a mut $ts)*);
+			type IntoIter = $IterMut<'a, $t1 $(, $ts)*, A>;
+
+			fn into_iter(self) -> Self::IntoIter {
+				self.iter_mut()
+			}
+		}
+
+		/// A mutable iterator over the tuples of a soa. See the `iter_mut` method for more information.
+		pub struct $IterMut<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a mut $name<$t1 $(, $ts)*, A>,
+			/// Index of the next tuple to be yielded from the front
+			start: usize,
+			/// Index one past the next tuple to be yielded from the back
+			end: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $IterMut<'a, $t1 $(, $ts)*, A> {
+			type Item = (&'a mut $t1 $(, &'a mut $ts)*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					// Each index is yielded at most once, so the references never alias
+					unsafe {
+						Some((
+							&mut *self.soa.$t1.as_ptr().add(index)
+							$(, &mut *self.soa.$ts.as_ptr().add(index))*
+						))
+					}
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $IterMut<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					unsafe {
+						Some((
+							&mut *self.soa.$t1.as_ptr().add(index)
+							$(, &mut *self.soa.$ts.as_ptr().add(index))*
+						))
+					}
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $IterMut<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $IterMut<'a, $t1 $(, $ts)*, A> {}
+
+		/// An iterator over chunks of a soa as tuples of slices. See the `chunks` method for more information.
+		pub struct $Chunks<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a $name<$t1 $(, $ts)*, A>,
+			/// Index of the first tuple of the next chunk to be yielded from the front
+			start: usize,
+			/// Index one past the last tuple of the next chunk to be yielded from the back
+			end: usize,
+			size: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Clone for $Chunks<'a, $t1 $(, $ts)*, A> {
+			fn clone(&self) -> Self {
+				$Chunks {
+					soa: self.soa,
+					start: self.start,
+					end: self.end,
+					size: self.size,
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $Chunks<'a, $t1 $(, $ts)*, A> {
+			type Item = (&'a [$t1] $(, &'a [$ts])*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let len = min(self.size, self.end - self.start);
+					let start = self.start;
+					self.start += len;
+					unsafe { Some(self.soa.slices_at(start, len)) }
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let remaining = self.end - self.start;
+				let len = remaining / self.size + (remaining % self.size != 0) as usize;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $Chunks<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					// The chunk at the back is the remainder, if there is one
+					let remainder = (self.end - self.start) % self.size;
+					let len = if remainder == 0 { self.size } else { remainder };
+					self.end -= len;
+					unsafe { Some(self.soa.slices_at(self.end, len)) }
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $Chunks<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $Chunks<'a, $t1 $(, $ts)*, A> {}
+
+		/// An iterator over chunks of a soa with exactly the same size. See the `chunks_exact` method for more information.
+		pub struct $ChunksExact<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a $name<$t1 $(, $ts)*, A>,
+			/// Index of the first tuple of the next chunk to be yielded from the front
+			start: usize,
+			/// Index one past the last tuple of the next chunk to be yielded from the back
+			end: usize,
+			size: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> $ChunksExact<'a, $t1 $(, $ts)*, A> {
+			/// Returns the tuples at the end of the soa which do not fill a whole chunk, as a tuple of slices.
+			pub fn remainder(&self) -> (&'a [$t1] $(, &'a [$ts])*) {
+				let start = self.soa.len - self.soa.len % self.size;
+				unsafe { self.soa.slices_at(start, self.soa.len - start) }
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Clone for $ChunksExact<'a, $t1 $(, $ts)*, A> {
+			fn clone(&self) -> Self {
+				$ChunksExact {
+					soa: self.soa,
+					start: self.start,
+					end: self.end,
+					size: self.size,
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $ChunksExact<'a, $t1 $(, $ts)*, A> {
+			type Item = (&'a [$t1] $(, &'a [$ts])*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let start = self.start;
+					self.start += self.size;
+					unsafe { Some(self.soa.slices_at(start, self.size)) }
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = (self.end - self.start) / self.size;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $ChunksExact<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= self.size;
+					unsafe { Some(self.soa.slices_at(self.end, self.size)) }
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $ChunksExact<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $ChunksExact<'a, $t1 $(, $ts)*, A> {}
+
+		/// An iterator over chunks of a soa, starting at the end. See the `rchunks` method for more information.
+		pub struct $RChunks<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a $name<$t1 $(, $ts)*, A>,
+			/// Index of the first tuple of the next chunk to be yielded from the back
+			start: usize,
+			/// Index one past the last tuple of the next chunk to be yielded from the front
+			end: usize,
+			size: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Clone for $RChunks<'a, $t1 $(, $ts)*, A> {
+			fn clone(&self) -> Self {
+				$RChunks {
+					soa: self.soa,
+					start: self.start,
+					end: self.end,
+					size: self.size,
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $RChunks<'a, $t1 $(, $ts)*, A> {
+			type Item = (&'a [$t1] $(, &'a [$ts])*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let len = min(self.size, self.end - self.start);
+					self.end -= len;
+					unsafe { Some(self.soa.slices_at(self.end, len)) }
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let remaining = self.end - self.start;
+				let len = remaining / self.size + (remaining % self.size != 0) as usize;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $RChunks<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					// The chunk at the front is the remainder, if there is one
+					let remainder = (self.end - self.start) % self.size;
+					let len = if remainder == 0 { self.size } else { remainder };
+					let start = self.start;
+					self.start += len;
+					unsafe { Some(self.soa.slices_at(start, len)) }
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $RChunks<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $RChunks<'a, $t1 $(, $ts)*, A> {}
+
+		/// An iterator over overlapping windows of a soa. See the `windows` method for more information.
+		pub struct $Windows<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a $name<$t1 $(, $ts)*, A>,
+			/// Index of the first tuple of the next window to be yielded from the front
+			start: usize,
+			/// Index one past the first tuple of the next window to be yielded from the back
+			end: usize,
+			size: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Clone for $Windows<'a, $t1 $(, $ts)*, A> {
+			fn clone(&self) -> Self {
+				$Windows {
+					soa: self.soa,
+					start: self.start,
+					end: self.end,
+					size: self.size,
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $Windows<'a, $t1 $(, $ts)*, A> {
+			type Item = (&'a [$t1] $(, &'a [$ts])*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let start = self.start;
+					self.start += 1;
+					unsafe { Some(self.soa.slices_at(start, self.size)) }
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $Windows<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					unsafe { Some(self.soa.slices_at(self.end, self.size)) }
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $Windows<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $Windows<'a, $t1 $(, $ts)*, A> {}
+
+		/// An iterator over runs of tuples of a soa. See the `chunk_by` method for more information.
+		pub struct $ChunkBy<'a, $t1: Sized $(, $ts: Sized)*, F, A: Allocator = Global> {
+			soa: &'a $name<$t1 $(, $ts)*, A>,
+			/// Index of the first tuple of the next run to be yielded from the front
+			start: usize,
+			/// Index one past the last tuple of the next run to be yielded from the back
+			end: usize,
+			pred: F,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*)) -> bool, A: Allocator> Iterator for $ChunkBy<'a, $t1 $(, $ts)*, F, A> {
+			type Item = (&'a [$t1] $(, &'a [$ts])*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let start = self.start;
+					let mut end = start + 1;
+					while end < self.end && unsafe { (self.pred)(self.soa.get_unchecked(end - 1), self.soa.get_unchecked(end)) } {
+						end += 1;
+					}
+					self.start = end;
+					unsafe { Some(self.soa.slices_at(start, end - start)) }
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(min(len, 1), Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*)) -> bool, A: Allocator> DoubleEndedIterator for $ChunkBy<'a, $t1 $(, $ts)*, F, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let end = self.end;
+					let mut start = end - 1;
+					while start > self.start && unsafe { (self.pred)(self.soa.get_unchecked(start - 1), self.soa.get_unchecked(start)) } {
+						start -= 1;
+					}
+					self.end = start;
+					unsafe { Some(self.soa.slices_at(start, end - start)) }
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*)) -> bool, A: Allocator> FusedIterator for $ChunkBy<'a, $t1 $(, $ts)*, F, A> {}
+
+		/// An iterator that moves tuples out of a soa. See the `into_iter` method for more information.
+		pub struct $IntoIter<$t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: $name<$t1 $(, $ts)*, A>,
+			/// Index of the next tuple to be yielded from the front
+			start: usize,
+			/// Index one past the next tuple to be yielded from the back
+			end: usize,
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $IntoIter<$t1 $(, $ts)*, A> {
+			type Item = ($t1 $(, $ts)*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					unsafe {
+						Some((
+							read(self.soa.$t1.as_ptr().add(index))
+							$(, read(self.soa.$ts.as_ptr().add(index)))*
+						))
+					}
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $IntoIter<$t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					unsafe {
+						Some((
+							read(self.soa.$t1.as_ptr().add(index))
+							$(, read(self.soa.$ts.as_ptr().add(index)))*
+						))
+					}
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> $IntoIter<$t1 $(, $ts)*, A> {
+			/// Returns a tuple of the fields of the tuples which have not yet been yielded, as slices.
+			pub fn as_slices(&self) -> (&[$t1] $(, &[$ts])*) {
+				let remaining = self.end - self.start;
+				unsafe {
+					(
+						from_raw_parts(self.soa.$t1.as_ptr().add(self.start), remaining)
+						$(, from_raw_parts(self.soa.$ts.as_ptr().add(self.start), remaining))*
+					)
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $IntoIter<$t1 $(, $ts)*, A> {}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $IntoIter<$t1 $(, $ts)*, A> {}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> Drop for $IntoIter<$t1 $(, $ts)*, A> {
+			fn drop(&mut self) {
+				// Drop any tuples which were not yielded. The allocation is freed when the soa is dropped.
+				let remaining = self.end - self.start;
+				unsafe {
+					drop_in_place(from_raw_parts_mut(self.soa.$t1.as_ptr().add(self.start), remaining));
+					$(drop_in_place(from_raw_parts_mut(self.soa.$ts.as_ptr().add(self.start), remaining));)*
+				}
+			}
+		}
+
+		/// A draining iterator over the tuples of a soa. See the `drain` method for more information.
+		pub struct $Drain<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator = Global> {
+			soa: &'a mut $name<$t1 $(, $ts)*, A>,
+			/// Index of the next tuple to be yielded from the front
+			start: usize,
+			/// Index one past the next tuple to be yielded from the back
+			end: usize,
+			tail_start: usize,
+			tail_len: usize,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Iterator for $Drain<'a, $t1 $(, $ts)*, A> {
+			type Item = ($t1 $(, $ts)*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					unsafe {
+						Some((
+							read(self.soa.$t1.as_ptr().add(index))
+							$(, read(self.soa.$ts.as_ptr().add(index)))*
+						))
+					}
+				}
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> DoubleEndedIterator for $Drain<'a, $t1 $(, $ts)*, A> {
+			fn next_back(&mut self) -> Option<Self::Item> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					unsafe {
+						Some((
+							read(self.soa.$t1.as_ptr().add(index))
+							$(, read(self.soa.$ts.as_ptr().add(index)))*
+						))
+					}
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> ExactSizeIterator for $Drain<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> FusedIterator for $Drain<'a, $t1 $(, $ts)*, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> Drop for $Drain<'a, $t1 $(, $ts)*, A> {
+			fn drop(&mut self) {
+				unsafe {
+					// Drop any tuples which were not yielded.
+					// If one of these panics the tail is leaked, which is safe.
+					let remaining = self.end - self.start;
+					drop_in_place(from_raw_parts_mut(self.soa.$t1.as_ptr().add(self.start), remaining));
+					$(drop_in_place(from_raw_parts_mut(self.soa.$ts.as_ptr().add(self.start), remaining));)*
+
+					// Close the gap left by the drained range
+					let len = self.soa.len;
+					if self.tail_start != len {
+						copy(self.soa.$t1.as_ptr().add(self.tail_start), self.soa.$t1.as_ptr().add(len), self.tail_len);
+						$(copy(self.soa.$ts.as_ptr().add(self.tail_start), self.soa.$ts.as_ptr().add(len), self.tail_len);)*
+					}
+					self.soa.len = len + self.tail_len;
+				}
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, A: Allocator> $Drain<'a, $t1 $(, $ts)*, A> {
+			/// Writes tuples from replace_with into the gap between the end of the soa and the tail.
+			/// Returns true if the gap was filled, or false if replace_with ran out first.
+			unsafe fn fill<I: Iterator<Item = ($t1 $(, $ts)*)>>(&mut self, replace_with: &mut I) -> bool {
+				while self.soa.len < self.tail_start {
+					match replace_with.next() {
+						Some(($t1 $(, $ts)*)) => {
+							write(self.soa.$t1.as_ptr().add(self.soa.len), $t1);
+							$(write(self.soa.$ts.as_ptr().add(self.soa.len), $ts);)*
+							self.soa.len += 1;
+						},
+						None => return false,
+					}
+				}
+				true
+			}
+
+			/// Reserves room for additional more tuples, then moves the tail back to widen the gap by that many.
+			unsafe fn move_tail(&mut self, additional: usize) {
+				// Reallocating only keeps the first len tuples, so count the gap and the tail while reserving.
+				// The gap is uninitialized, but is only ever copied as bytes. The length is restored before any panic.
+				let len = self.soa.len;
+				self.soa.len = self.tail_start + self.tail_len;
+				let result = self.soa.try_reserve(additional);
+				self.soa.len = len;
+				handle_reserve(result);
+
+				let new_tail_start = self.tail_start + additional;
+				copy(self.soa.$t1.as_ptr().add(self.tail_start), self.soa.$t1.as_ptr().add(new_tail_start), self.tail_len);
+				$(copy(self.soa.$ts.as_ptr().add(self.tail_start), self.soa.$ts.as_ptr().add(new_tail_start), self.tail_len);)*
+				self.tail_start = new_tail_start;
+			}
+		}
+
+		/// A splicing iterator for a soa. See the `splice` method for more information.
+		pub struct $Splice<'a, $t1: Sized $(, $ts: Sized)*, I: Iterator<Item = ($t1 $(, $ts)*)>, A: Allocator = Global> {
+			drain: $Drain<'a, $t1 $(, $ts)*, A>,
+			replace_with: I,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, I: Iterator<Item = ($t1 $(, $ts)*)>, A: Allocator> Iterator for $Splice<'a, $t1 $(, $ts)*, I, A> {
+			type Item = ($t1 $(, $ts)*);
+
+			fn next(&mut self) -> Option<Self::Item> { self.drain.next() }
+
+			fn size_hint(&self) -> (usize, Option<usize>) { self.drain.size_hint() }
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, I: Iterator<Item = ($t1 $(, $ts)*)>, A: Allocator> DoubleEndedIterator for $Splice<'a, $t1 $(, $ts)*, I, A> {
+			fn next_back(&mut self) -> Option<Self::Item> { self.drain.next_back() }
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, I: Iterator<Item = ($t1 $(, $ts)*)>, A: Allocator> ExactSizeIterator for $Splice<'a, $t1 $(, $ts)*, I, A> {}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, I: Iterator<Item = ($t1 $(, $ts)*)>, A: Allocator> Drop for $Splice<'a, $t1 $(, $ts)*, I, A> {
+			fn drop(&mut self) {
+				// Drop any removed tuples which were not yielded
+				for _ in &mut self.drain {}
+
+				// With no tail to move, the replacement can simply be appended
+				if self.drain.tail_len == 0 {
+					for value in self.replace_with.by_ref() {
+						self.drain.soa.push(value);
+					}
+					return;
+				}
+
+				// Fill the gap left by the removed tuples first, then move the tail once for the lower bound
+				// of the size hint, and buffer only the tuples beyond that. Dropping the drain closes any gap that remains.
+				unsafe {
+					if !self.drain.fill(&mut self.replace_with) {
+						return;
+					}
+
+					let (lower_bound, _) = self.replace_with.size_hint();
+					if lower_bound > 0 {
+						self.drain.move_tail(lower_bound);
+						if !self.drain.fill(&mut self.replace_with) {
+							return;
+						}
+					}
+
+					let mut collected = self.replace_with.by_ref().collect::<Vec<_>>().into_iter();
+					if collected.len() > 0 {
+						self.drain.move_tail(collected.len());
+						let filled = self.drain.fill(&mut collected);
+						debug_assert!(filled);
+					}
+				}
+			}
+		}
+
+		/// An iterator which removes the tuples of a soa that match a predicate.
+		/// See the `extract_if` method for more information.
+		pub struct $ExtractIf<'a, $t1: Sized $(, $ts: Sized)*, F: FnMut((&mut $t1 $(, &mut $ts)*)) -> bool, A: Allocator = Global> {
+			soa: &'a mut $name<$t1 $(, $ts)*, A>,
+			/// Index of the next tuple to be checked
+			index: usize,
+			/// Number of tuples removed so far
+			removed: usize,
+			old_len: usize,
+			pred: F,
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, F: FnMut((&mut $t1 $(, &mut $ts)*)) -> bool, A: Allocator> Iterator for $ExtractIf<'a, $t1 $(, $ts)*, F, A> {
+			type Item = ($t1 $(, $ts)*);
+
+			fn next(&mut self) -> Option<Self::Item> {
+				while self.index < self.old_len {
+					unsafe {
+						let index = self.index;
+						let $t1 = self.soa.$t1.as_ptr().add(index);
+						$(let $ts = self.soa.$ts.as_ptr().add(index);)*
+
+						let extract = (self.pred)((&mut *$t1 $(, &mut *$ts)*));
+						self.index += 1;
+
+						if extract {
+							self.removed += 1;
+							return Some((
+								read($t1)
+								$(, read($ts))*
+							));
+						} else if self.removed > 0 {
+							let dst = index - self.removed;
+							copy_nonoverlapping($t1, self.soa.$t1.as_ptr().add(dst), 1);
+							$(copy_nonoverlapping($ts, self.soa.$ts.as_ptr().add(dst), 1);)*
+						}
+					}
+				}
+				None
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				(0, Some(self.old_len - self.index))
+			}
+		}
+
+		impl<'a, $t1: Sized $(, $ts: Sized)*, F: FnMut((&mut $t1 $(, &mut $ts)*)) -> bool, A: Allocator> Drop for $ExtractIf<'a, $t1 $(, $ts)*, F, A> {
+			fn drop(&mut self) {
+				unsafe {
+					// Shift any unchecked tuples down over the removed ones
+					if self.index < self.old_len && self.removed > 0 {
+						let count = self.old_len - self.index;
+						let dst = self.index - self.removed;
+						copy(self.soa.$t1.as_ptr().add(self.index), self.soa.$t1.as_ptr().add(dst), count);
+						$(copy(self.soa.$ts.as_ptr().add(self.index), self.soa.$ts.as_ptr().add(dst), count);)*
+					}
+				}
+				self.soa.len = self.old_len - self.removed;
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, A: Allocator> Drop for $name<$t1 $(, $ts)*, A> {
+			fn drop(&mut self) {
+				self.clear(); // Drop owned items
+				self.dealloc()
+			}
+		}
+
+
+		impl<$t1: Clone + Sized $(, $ts: Clone + Sized)*, A: Allocator + Clone> Clone for $name<$t1 $(, $ts)*, A> {
+			fn clone(&self) -> Self {
+				let mut soa = Self::with_alignment_in(self.align, self.alloc.clone());
+				soa.reserve_exact(self.len);
+				unsafe {
+					// If a clone panics the clones already written are leaked, which is safe.
+					for i in 0..self.len {
+						write(soa.$t1.as_ptr().add(i), (&*(self.$t1.as_ptr().add(i))).clone());
+					}
+					$(
+						for i in 0..self.len {
+							write(soa.$ts.as_ptr().add(i), (&*(self.$ts.as_ptr().add(i))).clone());
+						}
+					)*
+				}
+				soa.len = self.len;
+				soa
+			}
+
+			/// Overwrites the contents of self with a clone of source,
+			/// reusing the existing allocation if it has sufficient capacity.
+			fn clone_from(&mut self, source: &Self) {
+				self.clear();
+				self.reserve_exact(source.len);
+
+				unsafe {
+					// If a clone panics the clones already written are leaked, which is safe.
+					for i in 0..source.len {
+						write(self.$t1.as_ptr().add(i), (&*(source.$t1.as_ptr().add(i))).clone());
+					}
+					$(
+						for i in 0..source.len {
+							write(self.$ts.as_ptr().add(i), (&*(source.$ts.as_ptr().add(i))).clone());
+						}
+					)*
+				}
+				self.len = source.len;
+			}
+		}
+
+		// The soa uniquely owns its tuples, just like Vec.
+		unsafe impl<$t1: Send $(, $ts: Send)*, A: Allocator + Send> Send for $name<$t1 $(, $ts)*, A> {}
+		unsafe impl<$t1: Sync $(, $ts: Sync)*, A: Allocator + Sync> Sync for $name<$t1 $(, $ts)*, A> {}
+
+		impl<$t1: Sized $(, $ts: Sized)*> Default for $name<$t1 $(, $ts)*> {
+			fn default() -> Self { Self::new() }
+		}
+
+		/// Two soas are equal if they have the same length and all their tuples are equal.
+		/// This compares one field at a time, which is the same as comparing tuples for any sane PartialEq.
+		impl<$t1: PartialEq $(, $ts: PartialEq)*, A: Allocator> PartialEq for $name<$t1 $(, $ts)*, A> {
+			fn eq(&self, other: &Self) -> bool {
+				self.len == other.len && self.slices() == other.slices()
+			}
+		}
+
+		impl<$t1: Eq $(, $ts: Eq)*, A: Allocator> Eq for $name<$t1 $(, $ts)*, A> {}
+
+		impl<$t1: PartialEq $(, $ts: PartialEq)*, A: Allocator> PartialEq<[($t1 $(, $ts)*)]> for $name<$t1 $(, $ts)*, A> {
+			fn eq(&self, other: &[($t1 $(, $ts)*)]) -> bool {
+				self.len == other.len() && other.iter().enumerate().all(|(i, ($t1 $(, $ts)*))| {
+					unsafe { self.get_unchecked(i) == ($t1 $(, $ts)*) }
+				})
+			}
+		}
+
+		impl<$t1: PartialEq $(, $ts: PartialEq)*, A: Allocator> PartialEq<Vec<($t1 $(, $ts)*)>> for $name<$t1 $(, $ts)*, A> {
+			fn eq(&self, other: &Vec<($t1 $(, $ts)*)>) -> bool {
+				*self == other[..]
+			}
+		}
+
+		/// Compares tuples lexicographically, the same as a Vec of tuples.
+		impl<$t1: PartialOrd $(, $ts: PartialOrd)*, A: Allocator> PartialOrd for $name<$t1 $(, $ts)*, A> {
+			fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+				for i in 0..min(self.len, other.len) {
+					let (a, b) = unsafe { (self.get_unchecked(i), other.get_unchecked(i)) };
+					match a.partial_cmp(&b) {
+						Some(Ordering::Equal) => (),
+						non_eq => return non_eq,
+					}
+				}
+				self.len.partial_cmp(&other.len)
+			}
+		}
+
+		/// Compares tuples lexicographically, the same as a Vec of tuples.
+		impl<$t1: Ord $(, $ts: Ord)*, A: Allocator> Ord for $name<$t1 $(, $ts)*, A> {
+			fn cmp(&self, other: &Self) -> Ordering {
+				for i in 0..min(self.len, other.len) {
+					let (a, b) = unsafe { (self.get_unchecked(i), other.get_unchecked(i)) };
+					match a.cmp(&b) {
+						Ordering::Equal => (),
+						non_eq => return non_eq,
+					}
+				}
+				self.len.cmp(&other.len)
+			}
+		}
+
+		/// Hashes the length followed by each tuple in order, the same as a Vec of tuples.
+		impl<$t1: Hash $(, $ts: Hash)*, A: Allocator> Hash for $name<$t1 $(, $ts)*, A> {
+			fn hash<H: Hasher>(&self, state: &mut H) {
+				state.write_usize(self.len);
+				for i in 0..self.len {
+					unsafe { self.get_unchecked(i) }.hash(state);
+				}
+			}
+		}
+
+		/// Serializes as a sequence of tuples, the same as a Vec of tuples.
+		#[cfg(feature = "serde")]
+		impl<$t1: serde::Serialize $(, $ts: serde::Serialize)*, A: Allocator> serde::Serialize for $name<$t1 $(, $ts)*, A> {
+			fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				use serde::ser::SerializeSeq;
+
+				let mut seq = serializer.serialize_seq(Some(self.len))?;
+				for i in 0..self.len {
+					seq.serialize_element(&unsafe { self.get_unchecked(i) })?;
+				}
+				seq.end()
+			}
+		}
+
+		/// Deserializes from a sequence of tuples, the same as a Vec of tuples.
+		#[cfg(feature = "serde")]
+		impl<'de, $t1: serde::Deserialize<'de> $(, $ts: serde::Deserialize<'de>)*> serde::Deserialize<'de> for $name<$t1 $(, $ts)*> {
+			fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				struct Visitor<$t1 $(, $ts)*>(PhantomData<($t1 $(, $ts)*)>);
+
+				impl<'de, $t1: serde::Deserialize<'de> $(, $ts: serde::Deserialize<'de>)*> serde::de::Visitor<'de> for Visitor<$t1 $(, $ts)*> {
+					type Value = $name<$t1 $(, $ts)*>;
+
+					fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+						f.write_str("a sequence of tuples")
+					}
+
+					fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+						// Don't trust the size hint of untrusted input too far
+						let mut soa = $name::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+						while let Some(value) = seq.next_element()? {
+							soa.push(value);
+						}
+						Ok(soa)
+					}
+				}
+
+				deserializer.deserialize_seq(Visitor(PhantomData))
+			}
+		}
+
+		#[cfg(feature = "serde")]
+		impl<$t1: serde::Serialize $(, $ts: serde::Serialize)*, A: Allocator> serde_columns::SerializeColumns for $name<$t1 $(, $ts)*, A> {
+			fn serialize_columns<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				serde::Serialize::serialize(&self.slices(), serializer)
+			}
+		}
+
+		#[cfg(feature = "serde")]
+		impl<'de, $t1: serde::Deserialize<'de> $(, $ts: serde::Deserialize<'de>)*> serde_columns::DeserializeColumns<'de> for $name<$t1 $(, $ts)*> {
+			fn deserialize_columns<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				let (mut $t1 $(, mut $ts)*): (Vec<$t1> $(, Vec<$ts>)*) = serde::Deserialize::deserialize(deserializer)?;
+				let len = $t1.len();
+				if $($ts.len() != len)||* {
+					return Err(serde::de::Error::custom("the columns do not all have the same length"));
+				}
+
+				// Move each column into the combined allocation
+				let mut soa = Self::with_capacity(len);
+				unsafe {
+					copy_nonoverlapping($t1.as_ptr(), soa.$t1.as_ptr(), len);
+					$t1.set_len(0);
+					$(
+						copy_nonoverlapping($ts.as_ptr(), soa.$ts.as_ptr(), len);
+						$ts.set_len(0);
+					)*
+				}
+				soa.len = len;
+				Ok(soa)
+			}
+		}
+
+		/// Generates a soa from a sequence of arbitrary tuples, for use in fuzz targets.
+		#[cfg(feature = "arbitrary")]
+		impl<'a, $t1: arbitrary::Arbitrary<'a> $(, $ts: arbitrary::Arbitrary<'a>)*> arbitrary::Arbitrary<'a> for $name<$t1 $(, $ts)*> {
+			fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+				u.arbitrary_iter()?.collect()
+			}
+
+			fn arbitrary_take_rest(u: arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+				u.arbitrary_take_rest_iter()?.collect()
+			}
+		}
+
+		impl<$t1: fmt::Debug $(, $ts: fmt::Debug)*, A: Allocator> fmt::Debug for $name<$t1 $(, $ts)*, A> {
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				let mut list = f.debug_list();
+				for i in 0..self.len {
+					list.entry(&unsafe { self.get_unchecked(i) });
+				}
+				list.finish()
+			}
+		}
+	};
+}
+
+soa!(Soa2, _2, Drain2, ExtractIf2, Splice2, Ref2, RefMut2, Iter2, IterMut2, IntoIter2, Chunks2, ChunksExact2, RChunks2, Windows2, ChunkBy2, T1, T2);
+soa!(Soa3, _3, Drain3, ExtractIf3, Splice3, Ref3, RefMut3, Iter3, IterMut3, IntoIter3, Chunks3, ChunksExact3, RChunks3, Windows3, ChunkBy3, T1, T2, T3);
+soa!(Soa4, _4, Drain4, ExtractIf4, Splice4, Ref4, RefMut4, Iter4, IterMut4, IntoIter4, Chunks4, ChunksExact4, RChunks4, Windows4, ChunkBy4, T1, T2, T3, T4);
+soa!(Soa5, _5, Drain5, ExtractIf5, Splice5, Ref5, RefMut5, Iter5, IterMut5, IntoIter5, Chunks5, ChunksExact5, RChunks5, Windows5, ChunkBy5, T1, T2, T3, T4, T5);
+soa!(Soa6, _6, Drain6, ExtractIf6, Splice6, Ref6, RefMut6, Iter6, IterMut6, IntoIter6, Chunks6, ChunksExact6, RChunks6, Windows6, ChunkBy6, T1, T2, T3, T4, T5, T6);
+soa!(Soa7, _7, Drain7, ExtractIf7, Splice7, Ref7, RefMut7, Iter7, IterMut7, IntoIter7, Chunks7, ChunksExact7, RChunks7, Windows7, ChunkBy7, T1, T2, T3, T4, T5, T6, T7);
+soa!(Soa8, _8, Drain8, ExtractIf8, Splice8, Ref8, RefMut8, Iter8, IterMut8, IntoIter8, Chunks8, ChunksExact8, RChunks8, Windows8, ChunkBy8, T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// This macro defines a soa which stores a small number of tuples inline before spilling to the heap.
+/// It need not be called often, just once per count of generic parameters.
+macro_rules! small_soa {
+	($name:ident, $Small:ident, $t1:ident, $($ts:ident),+) => {
+		/// A soa which stores up to N tuples inline, with an array per field inside the struct itself.
+		/// Pushing beyond N tuples moves all of them into a heap allocated soa. This avoids an allocation
+		/// for each of the many short soas which rarely grow beyond a handful of tuples.
+		pub struct $Small<$t1: Sized $(, $ts: Sized)*, const N: usize> {
+			/// Number of initialized tuples in the inline arrays. Always 0 once spilled.
+			len: usize,
+			$t1: [MaybeUninit<$t1>; N],
+			$($ts: [MaybeUninit<$ts>; N],)*
+			heap: Option<$name<$t1 $(, $ts)*>>,
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> $Small<$t1 $(, $ts)*, N> {
+			/// Creates a new small soa, which holds up to N tuples before allocating.
+			pub fn new() -> Self {
+				$Small {
+					len: 0,
+					$t1: [const { MaybeUninit::uninit() }; N],
+					$($ts: [const { MaybeUninit::uninit() }; N],)*
+					heap: None,
+				}
+			}
+
+			/// Returns true if the tuples have been moved to the heap.
+			pub fn spilled(&self) -> bool {
+				self.heap.is_some()
+			}
+
+			/// Returns the number of tuples in the soa.
+			pub fn len(&self) -> usize {
+				match &self.heap {
+					Some(heap) => heap.len(),
+					None => self.len,
+				}
+			}
+
+			/// Returns true if the soa contains no tuples.
+			pub fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+
+			/// Returns the number of tuples the soa can hold without reallocating, which is N until spilled.
+			pub fn capacity(&self) -> usize {
+				match &self.heap {
+					Some(heap) => heap.capacity(),
+					None => N,
+				}
+			}
+
+			/// Moves the inline tuples into a heap allocated soa with room for at least `additional` more.
+			fn spill(&mut self, additional: usize) {
+				let required = handle_reserve(self.len.checked_add(additional).ok_or(TryReserveError::CapacityOverflow));
+				self.spill_exact(required.max(N.saturating_mul(2)));
+			}
+
+			/// Moves the inline tuples into a heap allocated soa with a capacity of exactly `capacity`.
+			/// Requires capacity >= len
+			fn spill_exact(&mut self, capacity: usize) {
+				let mut heap = $name::with_capacity(capacity);
+				unsafe {
+					copy_nonoverlapping(self.$t1.as_ptr() as *const $t1, heap.$t1.as_ptr(), self.len);
+					$(copy_nonoverlapping(self.$ts.as_ptr() as *const $ts, heap.$ts.as_ptr(), self.len);)*
+				}
+				heap.len = self.len;
+				self.len = 0;
+				self.heap = Some(heap);
+			}
+
+			/// Reserves capacity for at least `additional` more tuples, moving to the heap if they would not fit inline.
+			///
+			/// # Panics:
+			///  * If the new capacity overflows usize
+			pub fn reserve(&mut self, additional: usize) {
+				match &mut self.heap {
+					Some(heap) => heap.reserve(additional),
+					None => if N - self.len < additional {
+						self.spill(additional);
+					},
+				}
+			}
+
+			/// Appends a tuple to the back of the soa, moving to the heap if the inline arrays are full.
+			pub fn push(&mut self, value: ($t1 $(, $ts)*)) {
+				if self.heap.is_none() && self.len == N {
+					self.spill(1);
+				}
+				match &mut self.heap {
+					Some(heap) => heap.push(value),
+					None => {
+						let ($t1 $(, $ts)*) = value;
+						self.$t1[self.len].write($t1);
+						$(self.$ts[self.len].write($ts);)*
+						self.len += 1;
+					},
+				}
+			}
+
+			/// Removes the last tuple from the soa and returns it, or None if it is empty.
+			pub fn pop(&mut self) -> Option<($t1 $(, $ts)*)> {
+				match &mut self.heap {
+					Some(heap) => heap.pop(),
+					None if self.len == 0 => None,
+					None => {
+						self.len -= 1;
+						unsafe {
+							Some((
+								self.$t1[self.len].assume_init_read()
+								$(, self.$ts[self.len].assume_init_read())*
+							))
+						}
+					},
+				}
+			}
+
+			/// Shortens the soa, keeping the first `len` tuples and dropping the rest.
+			/// If `len` is greater than the current length, this has no effect.
+			pub fn truncate(&mut self, len: usize) {
+				match &mut self.heap {
+					Some(heap) => heap.truncate(len),
+					None => if len < self.len {
+						let remaining = self.len - len;
+						// Set len first so that a panicking drop can't cause a double drop
+						self.len = len;
+						unsafe {
+							drop_in_place(from_raw_parts_mut(self.$t1.as_mut_ptr().add(len) as *mut $t1, remaining));
+							$(drop_in_place(from_raw_parts_mut(self.$ts.as_mut_ptr().add(len) as *mut $ts, remaining));)*
+						}
+					},
+				}
+			}
+
+			/// Clears the soa, removing all tuples. A spilled soa keeps its heap allocation.
+			pub fn clear(&mut self) {
+				self.truncate(0);
+			}
+
+			/// Returns a tuple of references to the fields at index, or None if the index is out of bounds.
+			pub fn get(&self, index: usize) -> Option<(&$t1 $(, &$ts)*)> {
+				let ($t1 $(, $ts)*) = self.slices();
+				if index < $t1.len() {
+					Some((&$t1[index] $(, &$ts[index])*))
+				} else {
+					None
+				}
+			}
+
+			/// Returns a tuple of mutable references to the fields at index, or None if the index is out of bounds.
+			pub fn get_mut(&mut self, index: usize) -> Option<(&mut $t1 $(, &mut $ts)*)> {
+				let ($t1 $(, $ts)*) = self.slices_mut();
+				if index < $t1.len() {
+					Some((&mut $t1[index] $(, &mut $ts[index])*))
+				} else {
+					None
+				}
+			}
+
+			/// Returns a tuple of slices, one for each field of the soa.
+			pub fn slices(&self) -> (&[$t1] $(, &[$ts])*) {
+				match &self.heap {
+					Some(heap) => heap.slices(),
+					None => unsafe {
+						(
+							from_raw_parts(self.$t1.as_ptr() as *const $t1, self.len)
+							$(, from_raw_parts(self.$ts.as_ptr() as *const $ts, self.len))*
+						)
+					},
+				}
+			}
+
+			/// Returns a tuple of mutable slices, one for each field of the soa.
+			pub fn slices_mut(&mut self) -> (&mut [$t1] $(, &mut [$ts])*) {
+				match &mut self.heap {
+					Some(heap) => heap.slices_mut(),
+					None => unsafe {
+						(
+							from_raw_parts_mut(self.$t1.as_mut_ptr() as *mut $t1, self.len)
+							$(, from_raw_parts_mut(self.$ts.as_mut_ptr() as *mut $ts, self.len))*
+						)
+					},
+				}
+			}
+
+			/// Converts into a heap allocated soa, which reuses the allocation if the tuples have already been moved to the heap.
+			pub fn into_soa(mut self) -> $name<$t1 $(, $ts)*> {
+				if self.heap.is_none() {
+					self.spill_exact(self.len);
+				}
+				self.heap.take().unwrap()
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> Drop for $Small<$t1 $(, $ts)*, N> {
+			fn drop(&mut self) {
+				// A spilled soa drops its own tuples
+				self.truncate(0);
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> Default for $Small<$t1 $(, $ts)*, N> {
+			fn default() -> Self { Self::new() }
+		}
+
+		impl<$t1: Clone $(, $ts: Clone)*, const N: usize> Clone for $Small<$t1 $(, $ts)*, N> {
+			fn clone(&self) -> Self {
+				let ($t1 $(, $ts)*) = self.slices();
+				let mut soa = Self::new();
+				soa.reserve($t1.len());
+				for i in 0..$t1.len() {
+					soa.push(($t1[i].clone() $(, $ts[i].clone())*));
+				}
+				soa
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> Extend<($t1 $(, $ts)*)> for $Small<$t1 $(, $ts)*, N> {
+			/// Appends the tuples of the iterator, reserving space up front for the lower bound of its size hint.
+			fn extend<I: IntoIterator<Item = ($t1 $(, $ts)*)>>(&mut self, iter: I) {
+				let iter = iter.into_iter();
+				self.reserve(iter.size_hint().0);
+				for value in iter {
+					self.push(value);
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> FromIterator<($t1 $(, $ts)*)> for $Small<$t1 $(, $ts)*, N> {
+			fn from_iter<I: IntoIterator<Item = ($t1 $(, $ts)*)>>(iter: I) -> Self {
+				let mut soa = Self::new();
+				soa.extend(iter);
+				soa
+			}
+		}
+
+		impl<$t1: PartialEq $(, $ts: PartialEq)*, const N: usize> PartialEq for $Small<$t1 $(, $ts)*, N> {
+			fn eq(&self, other: &Self) -> bool {
+				self.slices() == other.slices()
+			}
+		}
+
+		impl<$t1: Eq $(, $ts: Eq)*, const N: usize> Eq for $Small<$t1 $(, $ts)*, N> {}
+
+		impl<$t1: fmt::Debug $(, $ts: fmt::Debug)*, const N: usize> fmt::Debug for $Small<$t1 $(, $ts)*, N> {
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				let ($t1 $(, $ts)*) = self.slices();
+				let mut list = f.debug_list();
+				for i in 0..$t1.len() {
+					list.entry(&(&$t1[i] $(, &$ts[i])*));
+				}
+				list.finish()
+			}
+		}
+	};
+}
+
+small_soa!(Soa2, SmallSoa2, T1, T2);
+small_soa!(Soa3, SmallSoa3, T1, T2, T3);
+small_soa!(Soa4, SmallSoa4, T1, T2, T3, T4);
+small_soa!(Soa5, SmallSoa5, T1, T2, T3, T4, T5);
+small_soa!(Soa6, SmallSoa6, T1, T2, T3, T4, T5, T6);
+small_soa!(Soa7, SmallSoa7, T1, T2, T3, T4, T5, T6, T7);
+small_soa!(Soa8, SmallSoa8, T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// This macro defines a fixed capacity soa which stores its tuples inline and never allocates.
+/// It need not be called often, just once per count of generic parameters.
+macro_rules! soa_array {
+	($Array:ident, $t1:ident, $($ts:ident),+) => {
+		/// A soa with a fixed capacity of N tuples, with an array per field inside the struct itself.
+		/// It never allocates, so it can be kept on the stack or used as a bounded staging buffer.
+		pub struct $Array<$t1: Sized $(, $ts: Sized)*, const N: usize> {
+			len: usize,
+			$t1: [MaybeUninit<$t1>; N],
+			$($ts: [MaybeUninit<$ts>; N],)*
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> $Array<$t1 $(, $ts)*, N> {
+			/// Creates a new empty soa array.
+			pub const fn new() -> Self {
+				$Array {
+					len: 0,
+					$t1: [const { MaybeUninit::uninit() }; N],
+					$($ts: [const { MaybeUninit::uninit() }; N],)*
+				}
+			}
+
+			/// Returns the number of tuples in the soa array.
+			#[inline(always)]
+			pub fn len(&self) -> usize { self.len }
+
+			/// Returns true if the soa array contains no tuples.
+			#[inline(always)]
+			pub fn is_empty(&self) -> bool { self.len == 0 }
+
+			/// Returns true if the soa array holds N tuples, so no more can be pushed.
+			#[inline(always)]
+			pub fn is_full(&self) -> bool { self.len == N }
+
+			/// Returns the number of tuples the soa array can hold, which is always N.
+			#[inline(always)]
+			pub fn capacity(&self) -> usize { N }
+
+			/// Appends a tuple to the back of the soa array.
+			///
+			/// # Panics:
+			///  * If the soa array is full
+			pub fn push(&mut self, value: ($t1 $(, $ts)*)) {
+				if self.try_push(value).is_err() {
+					panic!("Soa array is full");
+				}
+			}
+
+			/// Appends a tuple to the back of the soa array, or returns it if the soa array is full.
+			pub fn try_push(&mut self, value: ($t1 $(, $ts)*)) -> Result<(), ($t1 $(, $ts)*)> {
+				if self.len == N {
+					return Err(value);
+				}
+
+				let ($t1 $(, $ts)*) = value;
+				self.$t1[self.len].write($t1);
+				$(self.$ts[self.len].write($ts);)*
+				self.len += 1;
+				Ok(())
+			}
+
+			/// Removes the last tuple from the soa array and returns it, or None if it is empty.
+			pub fn pop(&mut self) -> Option<($t1 $(, $ts)*)> {
+				if self.len == 0 {
+					None
+				} else {
+					self.len -= 1;
+					unsafe {
+						Some((
+							self.$t1[self.len].assume_init_read()
+							$(, self.$ts[self.len].assume_init_read())*
+						))
+					}
+				}
+			}
+
+			/// Shortens the soa array, keeping the first `len` tuples and dropping the rest.
+			/// If `len` is greater than the current length, this has no effect.
+			pub fn truncate(&mut self, len: usize) {
+				if len >= self.len {
+					return;
+				}
+
+				let remaining = self.len - len;
+				// Set len first so that a panicking drop can't cause a double drop
+				self.len = len;
+				unsafe {
+					drop_in_place(from_raw_parts_mut(self.$t1.as_mut_ptr().add(len) as *mut $t1, remaining));
+					$(drop_in_place(from_raw_parts_mut(self.$ts.as_mut_ptr().add(len) as *mut $ts, remaining));)*
+				}
+			}
+
+			/// Clears the soa array, removing all tuples.
+			pub fn clear(&mut self) {
+				self.truncate(0);
+			}
+
+			/// Returns a tuple of references to the fields at index, or None if the index is out of bounds.
+			pub fn get(&self, index: usize) -> Option<(&$t1 $(, &$ts)*)> {
+				let ($t1 $(, $ts)*) = self.slices();
+				if index < $t1.len() {
+					Some((&$t1[index] $(, &$ts[index])*))
+				} else {
+					None
+				}
+			}
+
+			/// Returns a tuple of mutable references to the fields at index, or None if the index is out of bounds.
+			pub fn get_mut(&mut self, index: usize) -> Option<(&mut $t1 $(, &mut $ts)*)> {
+				let ($t1 $(, $ts)*) = self.slices_mut();
+				if index < $t1.len() {
+					Some((&mut $t1[index] $(, &mut $ts[index])*))
+				} else {
+					None
+				}
+			}
+
+			/// Returns a tuple of slices, one for each field of the soa array.
+			pub fn slices(&self) -> (&[$t1] $(, &[$ts])*) {
+				unsafe {
+					(
+						from_raw_parts(self.$t1.as_ptr() as *const $t1, self.len)
+						$(, from_raw_parts(self.$ts.as_ptr() as *const $ts, self.len))*
+					)
+				}
+			}
+
+			/// Returns a tuple of mutable slices, one for each field of the soa array.
+			pub fn slices_mut(&mut self) -> (&mut [$t1] $(, &mut [$ts])*) {
+				unsafe {
+					(
+						from_raw_parts_mut(self.$t1.as_mut_ptr() as *mut $t1, self.len)
+						$(, from_raw_parts_mut(self.$ts.as_mut_ptr() as *mut $ts, self.len))*
+					)
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> Drop for $Array<$t1 $(, $ts)*, N> {
+			fn drop(&mut self) {
+				self.clear();
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> Default for $Array<$t1 $(, $ts)*, N> {
+			fn default() -> Self { Self::new() }
+		}
+
+		impl<$t1: Clone $(, $ts: Clone)*, const N: usize> Clone for $Array<$t1 $(, $ts)*, N> {
+			fn clone(&self) -> Self {
+				let ($t1 $(, $ts)*) = self.slices();
+				let mut array = Self::new();
+				for i in 0..$t1.len() {
+					array.push(($t1[i].clone() $(, $ts[i].clone())*));
+				}
+				array
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> Extend<($t1 $(, $ts)*)> for $Array<$t1 $(, $ts)*, N> {
+			/// Appends the tuples of the iterator.
+			///
+			/// # Panics:
+			///  * If the iterator yields more tuples than will fit
+			fn extend<I: IntoIterator<Item = ($t1 $(, $ts)*)>>(&mut self, iter: I) {
+				for value in iter {
+					self.push(value);
+				}
+			}
+		}
+
+		impl<$t1: Sized $(, $ts: Sized)*, const N: usize> FromIterator<($t1 $(, $ts)*)> for $Array<$t1 $(, $ts)*, N> {
+			/// # Panics:
+			///  * If the iterator yields more than N tuples
+			fn from_iter<I: IntoIterator<Item = ($t1 $(, $ts)*)>>(iter: I) -> Self {
+				let mut array = Self::new();
+				array.extend(iter);
+				array
+			}
+		}
+
+		impl<$t1: PartialEq $(, $ts: PartialEq)*, const N: usize> PartialEq for $Array<$t1 $(, $ts)*, N> {
+			fn eq(&self, other: &Self) -> bool {
+				self.slices() == other.slices()
+			}
+		}
+
+		impl<$t1: Eq $(, $ts: Eq)*, const N: usize> Eq for $Array<$t1 $(, $ts)*, N> {}
+
+		impl<$t1: fmt::Debug $(, $ts: fmt::Debug)*, const N: usize> fmt::Debug for $Array<$t1 $(, $ts)*, N> {
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				let ($t1 $(, $ts)*) = self.slices();
+				let mut list = f.debug_list();
+				for i in 0..$t1.len() {
+					list.entry(&(&$t1[i] $(, &$ts[i])*));
+				}
+				list.finish()
+			}
+		}
+	};
+}
+
+soa_array!(SoaArray2, T1, T2);
+soa_array!(SoaArray3, T1, T2, T3);
+soa_array!(SoaArray4, T1, T2, T3, T4);
+soa_array!(SoaArray5, T1, T2, T3, T4, T5);
+soa_array!(SoaArray6, T1, T2, T3, T4, T5, T6);
+soa_array!(SoaArray7, T1, T2, T3, T4, T5, T6, T7);
+soa_array!(SoaArray8, T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Reusable buffers for sorting soas. See the `sort_unstable_by_with` method of soas for more information.
+/// A scratch can be shared between soas of any type.
+#[derive(Default, Debug)]
+pub struct SortScratch {
+	indices: Vec<usize>,
+	lookup: Vec<usize>,
+}
+
+impl SortScratch {
+	/// Creates empty sort buffers, which grow to fit the largest soa they are used to sort
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+/// A key for `sort_unstable_by_key_radix`, which is converted to unsigned bits that sort in the same order as the key.
+/// Implemented for the integer types.
+pub trait RadixKey: Copy {
+	/// The number of low bytes of the bits which need to be sorted
+	const BYTES: usize;
+	fn to_radix_bits(self) -> u64;
+}
+
+macro_rules! radix_key {
+	($($t:ty),+) => {
+		$(impl RadixKey for $t {
+			const BYTES: usize = std::mem::size_of::<$t>();
+			fn to_radix_bits(self) -> u64 { self as u64 }
+		})+
+	};
+	($($t:ty => $u:ty),+) => {
+		$(impl RadixKey for $t {
+			const BYTES: usize = std::mem::size_of::<$t>();
+			// Flipping the sign bit orders negative numbers before positive numbers
+			fn to_radix_bits(self) -> u64 { (self as $u ^ (1 << (<$u>::BITS - 1))) as u64 }
+		})+
+	};
+}
+
+radix_key!(u8, u16, u32, u64, usize);
+radix_key!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize);
+
+/// Access to a single field of a soa by its index, eg: `Column::<0>::column(&soa)`
+/// This allows writing code which is generic over which field of a soa it reads or writes.
+///
+/// # Example
+/// ```
+/// # use soa_vec::{Column, Soa3};
+/// fn total<S: Column<1, Item = f32>>(soa: &S) -> f32 {
+///     soa.column().iter().sum()
+/// }
+///
+/// let mut soa = Soa3::new();
+/// soa.push((1u8, 2.0f32, 'a'));
+/// soa.push((3, 4.0, 'b'));
+/// assert_eq!(total(&soa), 6.0);
+///
+/// Column::<2>::column_mut(&mut soa)[0] = 'c';
+/// assert_eq!(soa.get(0), Some((&1, &2.0, &'c')));
+/// ```
+pub trait Column<const I: usize> {
+	/// The type of the field
+	type Item;
+	/// Returns the field as a slice
+	fn column(&self) -> &[Self::Item];
+	/// Returns the field as a mutable slice
+	fn column_mut(&mut self) -> &mut [Self::Item];
+}
+
+macro_rules! column {
+	($name:ident, [$($all:ident),+], $i:literal, $t:ident) => {
+		impl<$($all),+, A: Allocator> Column<$i> for $name<$($all),+, A> {
+			type Item = $t;
+
+			fn column(&self) -> &[$t] {
+				unsafe { from_raw_parts(self.$t.as_ptr(), self.len) }
+			}
+
+			fn column_mut(&mut self) -> &mut [$t] {
+				unsafe { from_raw_parts_mut(self.$t.as_ptr(), self.len) }
+			}
+		}
+	};
+}
+
+macro_rules! columns {
+	($name:ident, $all:tt, $($i:literal: $t:ident),+) => {
+		$(column!($name, $all, $i, $t);)+
+	};
+}
+
+columns!(Soa2, [T1, T2], 0: T1, 1: T2);
+columns!(Soa3, [T1, T2, T3], 0: T1, 1: T2, 2: T3);
+columns!(Soa4, [T1, T2, T3, T4], 0: T1, 1: T2, 2: T3, 3: T4);
+columns!(Soa5, [T1, T2, T3, T4, T5], 0: T1, 1: T2, 2: T3, 3: T4, 4: T5);
+columns!(Soa6, [T1, T2, T3, T4, T5, T6], 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6);
+columns!(Soa7, [T1, T2, T3, T4, T5, T6, T7], 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7);
+columns!(Soa8, [T1, T2, T3, T4, T5, T6, T7, T8], 0: T1, 1: T2, 2: T3, 3: T4, 4: T5, 5: T6, 6: T7, 7: T8);
+
+/// Proptest strategies for generating soas, eg: `soa2(any::<u8>(), any::<f32>(), 0..100)`
+/// Soas are generated from a vec of tuples, so shrinking removes whole tuples and keeps the fields together.
+#[cfg(feature = "proptest")]
+pub mod strategy {
+	use super::*;
+	use proptest::{collection::{vec, SizeRange}, strategy::Strategy};
+
+	macro_rules! strategy {
+		($fn:ident, $name:ident, $($s:ident: $S:ident),+) => {
+			/// Creates a strategy which generates a soa with a number of tuples in the size range,
+			/// using one strategy per field.
+			#[allow(clippy::too_many_arguments)]
+			pub fn $fn<$($S: Strategy),+>($($s: $S,)+ size: impl Into<SizeRange>) -> impl Strategy<Value = $name<$($S::Value),+>> {
+				vec(($($s,)+), size).prop_map($name::from)
+			}
+		};
+	}
+
+	strategy!(soa2, Soa2, s1: S1, s2: S2);
+	strategy!(soa3, Soa3, s1: S1, s2: S2, s3: S3);
+	strategy!(soa4, Soa4, s1: S1, s2: S2, s3: S3, s4: S4);
+	strategy!(soa5, Soa5, s1: S1, s2: S2, s3: S3, s4: S4, s5: S5);
+	strategy!(soa6, Soa6, s1: S1, s2: S2, s3: S3, s4: S4, s5: S5, s6: S6);
+	strategy!(soa7, Soa7, s1: S1, s2: S2, s3: S3, s4: S4, s5: S5, s6: S6, s7: S7);
+	strategy!(soa8, Soa8, s1: S1, s2: S2, s3: S3, s4: S4, s5: S5, s6: S6, s7: S7, s8: S8);
+}
+
+/// Zero-copy archiving with rkyv. A soa is archived as one archived vec per field,
+/// so the fields of an archived soa can be read as slices directly from the archive bytes.
+#[cfg(feature = "rkyv")]
+pub mod archive {
+	use super::*;
+	use rkyv::{out_field, ser::{ScratchSpace, Serializer}, vec::{ArchivedVec, VecResolver}, Archive, Archived, Deserialize, Fallible, Serialize};
+
+	macro_rules! archive {
+		($name:ident, $Archived:ident, $Resolver:ident, $t1:ident, $($ts:ident),+) => {
+			/// The archived form of a soa, with one archived vec per field.
+			#[repr(C)]
+			pub struct $Archived<$t1: Archive $(, $ts: Archive)*> {
+				$t1: ArchivedVec<Archived<$t1>>,
+				$($ts: ArchivedVec<Archived<$ts>>,)*
+			}
+
+			impl<$t1: Archive $(, $ts: Archive)*> $Archived<$t1 $(, $ts)*> {
+				/// The number of tuples in the archived soa
+				pub fn len(&self) -> usize {
+					self.$t1.len()
+				}
+
+				pub fn is_empty(&self) -> bool {
+					self.$t1.is_empty()
+				}
+
+				/// Gets the archived fields as slices, without deserializing
+				pub fn slices(&self) -> (&[Archived<$t1>] $(, &[Archived<$ts>])*) {
+					(self.$t1.as_slice() $(, self.$ts.as_slice())*)
+				}
+			}
+
+			/// The resolver for archiving a soa, with one vec resolver per field.
+			pub struct $Resolver {
+				$t1: VecResolver,
+				$($ts: VecResolver,)*
+			}
+
+			impl<$t1: Archive $(, $ts: Archive)*> Archive for $name<$t1 $(, $ts)*> {
+				type Archived = $Archived<$t1 $(, $ts)*>;
+				type Resolver = $Resolver;
+
+				unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+					let (fp, fo) = out_field!(out.$t1);
+					ArchivedVec::resolve_from_len(self.len, pos + fp, resolver.$t1, fo);
+					$(
+						let (fp, fo) = out_field!(out.$ts);
+						ArchivedVec::resolve_from_len(self.len, pos + fp, resolver.$ts, fo);
+					)*
+				}
+			}
+
+			impl<S: ScratchSpace + Serializer + ?Sized, $t1: Serialize<S> $(, $ts: Serialize<S>)*> Serialize<S> for $name<$t1 $(, $ts)*> {
+				fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+					let ($t1 $(, $ts)*) = self.slices();
+					Ok($Resolver {
+						$t1: ArchivedVec::serialize_from_slice($t1, serializer)?,
+						$($ts: ArchivedVec::serialize_from_slice($ts, serializer)?,)*
+					})
+				}
+			}
+
+			impl<D: Fallible + ?Sized, $t1: Archive $(, $ts: Archive)*> Deserialize<$name<$t1 $(, $ts)*>, D> for $Archived<$t1 $(, $ts)*>
+			where Archived<$t1>: Deserialize<$t1, D> $(, Archived<$ts>: Deserialize<$ts, D>)* {
+				fn deserialize(&self, deserializer: &mut D) -> Result<$name<$t1 $(, $ts)*>, D::Error> {
+					let len = self.len();
+					let mut soa = $name::with_capacity(len);
+					for i in 0..len {
+						soa.push((self.$t1[i].deserialize(deserializer)? $(, self.$ts[i].deserialize(deserializer)?)*));
+					}
+					Ok(soa)
+				}
+			}
+		};
+	}
+
+	archive!(Soa2, ArchivedSoa2, Soa2Resolver, T1, T2);
+	archive!(Soa3, ArchivedSoa3, Soa3Resolver, T1, T2, T3);
+	archive!(Soa4, ArchivedSoa4, Soa4Resolver, T1, T2, T3, T4);
+	archive!(Soa5, ArchivedSoa5, Soa5Resolver, T1, T2, T3, T4, T5);
+	archive!(Soa6, ArchivedSoa6, Soa6Resolver, T1, T2, T3, T4, T5, T6);
+	archive!(Soa7, ArchivedSoa7, Soa7Resolver, T1, T2, T3, T4, T5, T6, T7);
+	archive!(Soa8, ArchivedSoa8, Soa8Resolver, T1, T2, T3, T4, T5, T6, T7, T8);
+}
+
+/// Parallel iteration with rayon. The fields are zipped with rayon's `MultiZip`,
+/// so the parallel iterators are indexed and split the range of tuples between threads.
+#[cfg(feature = "rayon")]
+pub mod par {
+	use super::*;
+	use rayon::{iter::{plumbing::*, MultiZip}, prelude::*};
+	use std::collections::LinkedList;
+
+	macro_rules! par {
+		($name:ident, $IntoParIter:ident, $DrainProducer:ident, $SliceDrain:ident, $t1:ident, $($ts:ident),+) => {
+			impl<$t1: Sized $(, $ts: Sized)*> $name<$t1 $(, $ts)*> {
+				/// Returns a parallel iterator over the tuples of the soa, with a reference to each field.
+				pub fn par_iter(&self) -> MultiZip<(rayon::slice::Iter<'_, $t1> $(, rayon::slice::Iter<'_, $ts>)*)> where $t1: Sync $(, $ts: Sync)* {
+					let ($t1 $(, $ts)*) = self.slices();
+					($t1.par_iter() $(, $ts.par_iter())*).into_par_iter()
+				}
+
+				/// Returns a parallel iterator over the tuples of the soa, with a mutable reference to each field.
+				pub fn par_iter_mut(&mut self) -> MultiZip<(rayon::slice::IterMut<'_, $t1> $(, rayon::slice::IterMut<'_, $ts>)*)> where $t1: Send $(, $ts: Send)* {
+					let ($t1 $(, $ts)*) = self.slices_mut();
+					($t1.par_iter_mut() $(, $ts.par_iter_mut())*).into_par_iter()
+				}
+
+				/// Returns a parallel iterator over chunks of size tuples at a time, as tuples of slices.
+				/// The last chunk is shorter if size does not divide the length.
+				///
+				/// # Panics:
+				///  * If size is 0
+				pub fn par_chunks(&self, size: usize) -> MultiZip<(rayon::slice::Chunks<'_, $t1> $(, rayon::slice::Chunks<'_, $ts>)*)> where $t1: Sync $(, $ts: Sync)* {
+					let ($t1 $(, $ts)*) = self.slices();
+					($t1.par_chunks(size) $(, $ts.par_chunks(size))*).into_par_iter()
+				}
+
+				/// Returns a parallel iterator over chunks of size tuples at a time, as tuples of mutable slices.
+				/// The last chunk is shorter if size does not divide the length.
+				///
+				/// # Panics:
+				///  * If size is 0
+				pub fn par_chunks_mut(&mut self, size: usize) -> MultiZip<(rayon::slice::ChunksMut<'_, $t1> $(, rayon::slice::ChunksMut<'_, $ts>)*)> where $t1: Send $(, $ts: Send)* {
+					let ($t1 $(, $ts)*) = self.slices_mut();
+					($t1.par_chunks_mut(size) $(, $ts.par_chunks_mut(size))*).into_par_iter()
+				}
+
+				/// Sorts the soa in parallel, keeping related data together.
+				/// The order of the tuples is sorted in parallel and then applied to all of the fields at once.
+				pub fn par_sort_unstable_by<F: Fn((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*)) -> Ordering + Sync>(&mut self, f: F) where $t1: Sync $(, $ts: Sync)* {
+					if self.len < 2 {
+						return;
+					}
+					let mut indices: Vec<usize> = (0..self.len).collect();
+
+					let soa = &*self;
+					indices.par_sort_unstable_by(|a, b| unsafe { f(soa.get_unchecked(*a), soa.get_unchecked(*b)) });
+
+					self.apply_sorted_indices(&mut indices, &mut Vec::new());
+				}
+			}
+
+			impl<$t1: Send $(, $ts: Send)*> IntoParallelIterator for $name<$t1 $(, $ts)*> {
+				type Iter = $IntoParIter<$t1 $(, $ts)*>;
+				type Item = ($t1 $(, $ts)*);
+
+				/// Creates a parallel iterator which moves each tuple out of the soa.
+				fn into_par_iter(self) -> Self::Iter {
+					$IntoParIter { soa: self }
+				}
+			}
+
+			/// A parallel iterator that moves tuples out of a soa. See the `into_par_iter` method for more information.
+			pub struct $IntoParIter<$t1: Send $(, $ts: Send)*> {
+				soa: $name<$t1 $(, $ts)*>,
+			}
+
+			impl<$t1: Send $(, $ts: Send)*> ParallelIterator for $IntoParIter<$t1 $(, $ts)*> {
+				type Item = ($t1 $(, $ts)*);
+
+				fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+					bridge(self, consumer)
+				}
+
+				fn opt_len(&self) -> Option<usize> {
+					Some(self.soa.len)
+				}
+			}
+
+			impl<$t1: Send $(, $ts: Send)*> IndexedParallelIterator for $IntoParIter<$t1 $(, $ts)*> {
+				fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+					bridge(self, consumer)
+				}
+
+				fn len(&self) -> usize {
+					self.soa.len
+				}
+
+				fn with_producer<CB: ProducerCallback<Self::Item>>(mut self, callback: CB) -> CB::Output {
+					let len = self.soa.len;
+					// The tuples are owned by the producer, which leaves only the allocation for the soa to free.
+					self.soa.len = 0;
+					let producer = unsafe {
+						$DrainProducer {
+							$t1: from_raw_parts_mut(self.soa.$t1.as_ptr(), len),
+							$($ts: from_raw_parts_mut(self.soa.$ts.as_ptr(), len),)*
+						}
+					};
+					callback.callback(producer)
+				}
+			}
+
+			/// Splits the fields of a soa between threads, moving the tuples out of each part.
+			struct $DrainProducer<'a, $t1 $(, $ts)*> {
+				$t1: &'a mut [$t1],
+				$($ts: &'a mut [$ts],)*
+			}
+
+			impl<'a, $t1: Send $(, $ts: Send)*> Producer for $DrainProducer<'a, $t1 $(, $ts)*> {
+				type Item = ($t1 $(, $ts)*);
+				type IntoIter = $SliceDrain<'a, $t1 $(, $ts)*>;
+
+				fn into_iter(mut self) -> Self::IntoIter {
+					// Leave empty slices behind so that the drain is responsible for dropping the tuples
+					$SliceDrain {
+						$t1: std::mem::take(&mut self.$t1).iter_mut(),
+						$($ts: std::mem::take(&mut self.$ts).iter_mut(),)*
+					}
+				}
+
+				fn split_at(mut self, index: usize) -> (Self, Self) {
+					let ($t1 $(, $ts)*) = (
+						std::mem::take(&mut self.$t1).split_at_mut(index)
+						$(, std::mem::take(&mut self.$ts).split_at_mut(index))*
+					);
+					(
+						$DrainProducer { $t1: $t1.0, $($ts: $ts.0,)* },
+						$DrainProducer { $t1: $t1.1, $($ts: $ts.1,)* },
+					)
+				}
+			}
+
+			impl<'a, $t1 $(, $ts)*> Drop for $DrainProducer<'a, $t1 $(, $ts)*> {
+				fn drop(&mut self) {
+					// Drop any tuples of a part which was never iterated
+					unsafe {
+						drop_in_place(std::mem::take::<&mut [$t1]>(&mut self.$t1));
+						$(drop_in_place(std::mem::take::<&mut [$ts]>(&mut self.$ts));)*
+					}
+				}
+			}
+
+			/// Moves the tuples out of one part of a soa for a single thread.
+			struct $SliceDrain<'a, $t1 $(, $ts)*> {
+				$t1: IterMut<'a, $t1>,
+				$($ts: IterMut<'a, $ts>,)*
+			}
+
+			impl<'a, $t1 $(, $ts)*> Iterator for $SliceDrain<'a, $t1 $(, $ts)*> {
+				type Item = ($t1 $(, $ts)*);
+
+				fn next(&mut self) -> Option<Self::Item> {
+					// All of the fields have the same length, so they run out together
+					let $t1 = self.$t1.next()?;
+					$(let $ts = self.$ts.next()?;)*
+					unsafe { Some((read($t1) $(, read($ts))*)) }
+				}
+
+				fn size_hint(&self) -> (usize, Option<usize>) {
+					self.$t1.size_hint()
+				}
+			}
+
+			impl<'a, $t1 $(, $ts)*> DoubleEndedIterator for $SliceDrain<'a, $t1 $(, $ts)*> {
+				fn next_back(&mut self) -> Option<Self::Item> {
+					let $t1 = self.$t1.next_back()?;
+					$(let $ts = self.$ts.next_back()?;)*
+					unsafe { Some((read($t1) $(, read($ts))*)) }
+				}
+			}
+
+			impl<'a, $t1 $(, $ts)*> ExactSizeIterator for $SliceDrain<'a, $t1 $(, $ts)*> {}
+
+			impl<'a, $t1 $(, $ts)*> Drop for $SliceDrain<'a, $t1 $(, $ts)*> {
+				fn drop(&mut self) {
+					// Drop any tuples which were not yielded
+					unsafe {
+						drop_in_place(std::mem::replace(&mut self.$t1, [].iter_mut()).into_slice());
+						$(drop_in_place(std::mem::replace(&mut self.$ts, [].iter_mut()).into_slice());)*
+					}
+				}
+			}
+
+			impl<$t1: Send $(, $ts: Send)*> FromParallelIterator<($t1 $(, $ts)*)> for $name<$t1 $(, $ts)*> {
+				fn from_par_iter<I: IntoParallelIterator<Item = ($t1 $(, $ts)*)>>(par_iter: I) -> Self {
+					let mut soa = Self::new();
+					soa.par_extend(par_iter);
+					soa
+				}
+			}
+
+			impl<$t1: Send $(, $ts: Send)*> ParallelExtend<($t1 $(, $ts)*)> for $name<$t1 $(, $ts)*> {
+				/// Collects a soa on each thread, then reserves once and moves the tuples of each onto the end of this soa.
+				fn par_extend<I: IntoParallelIterator<Item = ($t1 $(, $ts)*)>>(&mut self, par_iter: I) {
+					let shards = par_iter
+						.into_par_iter()
+						.fold($name::new, |mut soa, tuple| {
+							soa.push(tuple);
+							soa
+						})
+						.map(|soa| {
+							let mut list = LinkedList::new();
+							list.push_back(soa);
+							list
+						})
+						.reduce(LinkedList::new, |mut lh, mut rh| {
+							lh.append(&mut rh);
+							lh
+						});
+
+					self.reserve(shards.iter().map(|shard| shard.len).sum());
+					for mut shard in shards {
+						self.append(&mut shard);
+					}
+				}
+			}
+		};
+	}
+
+	par!(Soa2, IntoParIter2, DrainProducer2, SliceDrain2, T1, T2);
+	par!(Soa3, IntoParIter3, DrainProducer3, SliceDrain3, T1, T2, T3);
+	par!(Soa4, IntoParIter4, DrainProducer4, SliceDrain4, T1, T2, T3, T4);
+	par!(Soa5, IntoParIter5, DrainProducer5, SliceDrain5, T1, T2, T3, T4, T5);
+	par!(Soa6, IntoParIter6, DrainProducer6, SliceDrain6, T1, T2, T3, T4, T5, T6);
+	par!(Soa7, IntoParIter7, DrainProducer7, SliceDrain7, T1, T2, T3, T4, T5, T6, T7);
+	par!(Soa8, IntoParIter8, DrainProducer8, SliceDrain8, T1, T2, T3, T4, T5, T6, T7, T8);
+}
+
+/// Building soas inside a bumpalo arena. Any soa can allocate from an arena with `new_in(&bump)`,
+/// and the `FrameSoa` types are for scratch data which is discarded along with the arena.
+#[cfg(feature = "bumpalo")]
+pub mod bump {
+	use super::*;
+	use bumpalo::Bump;
+	use std::mem::ManuallyDrop;
+
+	macro_rules! bump {
+		($name:ident, $Frame:ident, $t1:ident, $($ts:ident),+) => {
+			impl<'bump, $t1: Sized $(, $ts: Sized)*> $name<$t1 $(, $ts)*, &'bump Bump> {
+				/// Consumes the soa, returning a tuple of mutable slices over each field which live as long as the arena.
+				/// The destructors of the tuples will not be run, even when the arena is dropped.
+				pub fn into_bump_slices(self) -> (&'bump mut [$t1] $(, &'bump mut [$ts])*) {
+					self.leak()
+				}
+			}
+
+			/// A soa allocated in a bump arena which neither drops its tuples nor frees its memory when it is dropped.
+			/// This makes discarding per-frame scratch data free, as the memory is reclaimed when the arena is reset.
+			/// Derefs to the soa, so the full soa API is available.
+			pub struct $Frame<'bump, $t1: Sized $(, $ts: Sized)*> {
+				soa: ManuallyDrop<$name<$t1 $(, $ts)*, &'bump Bump>>,
+			}
+
+			impl<'bump, $t1: Sized $(, $ts: Sized)*> $Frame<'bump, $t1 $(, $ts)*> {
+				/// Creates a new frame soa with a capacity of 0 which will allocate from bump
+				pub fn new_in(bump: &'bump Bump) -> Self {
+					Self { soa: ManuallyDrop::new($name::new_in(bump)) }
+				}
+
+				/// Creates a new frame soa with room for at least `capacity` tuples, allocated from bump
+				pub fn with_capacity_in(capacity: usize, bump: &'bump Bump) -> Self {
+					Self { soa: ManuallyDrop::new($name::with_capacity_in(capacity, bump)) }
+				}
+
+				/// Consumes the frame soa, returning a tuple of mutable slices over each field which live as long as the arena.
+				pub fn into_bump_slices(self) -> (&'bump mut [$t1] $(, &'bump mut [$ts])*) {
+					ManuallyDrop::into_inner(self.soa).into_bump_slices()
+				}
+			}
+
+			impl<'bump, $t1: Sized $(, $ts: Sized)*> Deref for $Frame<'bump, $t1 $(, $ts)*> {
+				type Target = $name<$t1 $(, $ts)*, &'bump Bump>;
+
+				fn deref(&self) -> &Self::Target {
+					&self.soa
+				}
+			}
+
+			impl<'bump, $t1: Sized $(, $ts: Sized)*> DerefMut for $Frame<'bump, $t1 $(, $ts)*> {
+				fn deref_mut(&mut self) -> &mut Self::Target {
+					&mut self.soa
+				}
+			}
+
+			impl<'bump, $t1: fmt::Debug $(, $ts: fmt::Debug)*> fmt::Debug for $Frame<'bump, $t1 $(, $ts)*> {
+				fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+					fmt::Debug::fmt(&*self.soa, f)
+				}
+			}
+		};
+	}
+
+	bump!(Soa2, FrameSoa2, T1, T2);
+	bump!(Soa3, FrameSoa3, T1, T2, T3);
+	bump!(Soa4, FrameSoa4, T1, T2, T3, T4);
+	bump!(Soa5, FrameSoa5, T1, T2, T3, T4, T5);
+	bump!(Soa6, FrameSoa6, T1, T2, T3, T4, T5, T6);
+	bump!(Soa7, FrameSoa7, T1, T2, T3, T4, T5, T6, T7);
+	bump!(Soa8, FrameSoa8, T1, T2, T3, T4, T5, T6, T7, T8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testdrop::TestDrop;
+
+    #[test]
+    fn layouts_do_not_overlap() {
+        // Trying with both (small, large) and (large, small) to ensure nothing bleeds into anything else.
+        // This verifies we correctly chunk the slices from the larger allocations.
+        let mut soa_ab = Soa2::new();
+        let mut soa_ba = Soa2::new();
+
+        fn ab(v: usize) -> (u8, f64) {
+            (v as u8, 200.0 + ((v as f64) / 200.0))
+        }
+
+        fn ba(v: usize) -> (f64, u8) {
+            (15.0 + ((v as f64) / 16.0), (200 - v) as u8)
+        }
+
+        // Combined with the tests inside, also verifies that we are copying the data on grow correctly.
+        for i in 0..100 {
+            soa_ab.push(ab(i));
+            let (a, b) = soa_ab.slices();
+            assert_eq!(i + 1, a.len());
+            assert_eq!(i + 1, b.len());
+            assert_eq!(ab(0).0, a[0]);
+            assert_eq!(ab(0).1, b[0]);
+            assert_eq!(ab(i).0, a[i]);
+            assert_eq!(ab(i).1, b[i]);
+
+            soa_ba.push(ba(i));
+            let (b, a) = soa_ba.slices();
+            assert_eq!(i + 1, a.len());
+            assert_eq!(i + 1, b.len());
+            assert_eq!(ba(0).0, b[0]);
+            assert_eq!(ba(0).1, a[0]);
+            assert_eq!(ba(i).0, b[i]);
+            assert_eq!(ba(i).1, a[i]);
+        }
+    }
+
+    #[test]
+    fn sort() {
+        let mut soa = Soa3::new();
+
+        soa.push((3, 'a', 4.0));
+        soa.push((1, 'b', 5.0));
+        soa.push((2, 'c', 6.0));
+
+        soa.sort_unstable_by(|(a1, _, _), (a2, _, _)| a1.cmp(a2));
+
+        assert_eq!(soa.get(0), Some((&1, &('b'), &5.0)));
+        assert_eq!(soa.get(1), Some((&2, &('c'), &6.0)));
+        assert_eq!(soa.get(2), Some((&3, &('a'), &4.0)));
+    }
+
+    #[test]
+    fn merge_sorted() {
+        let lh: Soa2<u32, char> = vec![(1, 'a'), (3, 'a'), (5, 'a')].into();
+        let rh: Soa2<u32, char> = vec![(0, 'b'), (3, 'b'), (6, 'b'), (7, 'b')].into();
+
+        let merged = lh.merge_sorted(rh, |(a, _), (b, _)| a.cmp(b));
+        assert_eq!(merged, [(0, 'b'), (1, 'a'), (3, 'a'), (3, 'b'), (5, 'a'), (6, 'b'), (7, 'b')][..]);
+        assert_eq!(merged.capacity(), 7);
+    }
+
+    #[test]
+    fn partition() {
+        let td = TestDrop::new();
+        let soa: Soa2<u32, _> = (0..6).map(|i| (i, td.new_item().1)).collect();
+
+        let (even, odd) = soa.partition(|(n, _)| n % 2 == 0);
+        assert_eq!(even.slices().0, [0, 2, 4]);
+        assert_eq!(odd.slices().0, [1, 3, 5]);
+        assert_eq!(td.num_dropped_items(), 0);
+        drop((even, odd));
+        assert_eq!(td.num_dropped_items(), 6);
+    }
+
+    #[test]
+    fn contains_position() {
+        let soa: Soa2<u32, String> = vec![(1, "a".to_owned()), (2, "b".to_owned()), (3, "b".to_owned())].into();
+
+        assert!(soa.contains(&(2, "b".to_owned())));
+        assert!(!soa.contains(&(2, "a".to_owned())));
+        assert_eq!(soa.position(|(_, s)| s == "b"), Some(1));
+        assert_eq!(soa.position(|(n, _)| *n > 3), None);
+    }
+
+    #[test]
+    fn min_max_by() {
+        let soa: Soa2<&str, f32> = vec![("a", 2.0), ("b", -1.0), ("c", 5.0), ("d", -1.0), ("e", 5.0)].into();
+        let by_value = |(_, a): (&&str, &f32), (_, b): (&&str, &f32)| a.partial_cmp(b).unwrap();
+
+        assert_eq!(soa.position_min_by(by_value), Some(1));
+        assert_eq!(soa.position_max_by(by_value), Some(4));
+        assert_eq!(soa.min_by(by_value), Some((&"b", &-1.0)));
+        assert_eq!(soa.max_by(by_value), Some((&"e", &5.0)));
+        assert_eq!(Soa2::<&str, f32>::new().min_by(by_value), None);
+    }
+
+    #[test]
+    fn sort_unstable_by_with() {
+        let mut scratch = SortScratch::new();
+        let mut a: Soa2<u32, char> = vec![(3, 'c'), (1, 'a'), (2, 'b')].into();
+        let mut b: Soa3<i8, u8, u8> = vec![(-1, 0, 0), (-3, 1, 1), (0, 2, 2), (-2, 3, 3)].into();
+
+        for _ in 0..2 {
+            a.sort_unstable_by_with(&mut scratch, |(x, _), (y, _)| y.cmp(x));
+            b.sort_unstable_by_with(&mut scratch, |(x, ..), (y, ..)| x.cmp(y));
+        }
+        assert_eq!(a, [(3, 'c'), (2, 'b'), (1, 'a')][..]);
+        assert_eq!(b.slices().1, [1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn sort_ord() {
+        let mut soa: Soa2<u8, char> = vec![(2, 'b'), (1, 'z'), (2, 'a'), (0, 'c')].into();
+        soa.sort_unstable();
+        assert_eq!(soa, [(0, 'c'), (1, 'z'), (2, 'a'), (2, 'b')][..]);
+
+        // Stable sorting keeps the order of equal tuples
+        let mut soa: Soa2<u8, char> = vec![(2, 'b'), (1, 'z'), (2, 'a'), (0, 'c')].into();
+        soa.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(soa, [(0, 'c'), (1, 'z'), (2, 'b'), (2, 'a')][..]);
+        soa.sort();
+        assert_eq!(soa, [(0, 'c'), (1, 'z'), (2, 'a'), (2, 'b')][..]);
+    }
+
+    #[test]
+    fn sort_unstable_by_key() {
+        let mut soa: Soa3<&str, u32, bool> = vec![("c", 3, true), ("a", 1, false), ("b", 2, true)].into();
+        soa.sort_unstable_by_key(|(_, n, _)| *n);
+        assert_eq!(soa, [("a", 1, false), ("b", 2, true), ("c", 3, true)][..]);
+        soa.sort_unstable_by_key(|(_, n, _)| std::cmp::Reverse(*n));
+        assert_eq!(soa.slices().0, ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn sort_by_cached_key() {
+        let mut soa: Soa2<String, u32> = vec![("Bb".to_owned(), 0), ("a".to_owned(), 1), ("bB".to_owned(), 2), ("C".to_owned(), 3)].into();
+        let mut calls = 0;
+        soa.sort_by_cached_key(|(s, _)| {
+            calls += 1;
+            s.to_lowercase()
+        });
+        assert_eq!(calls, 4);
+        assert_eq!(soa.slices().1, [1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn select_nth_unstable_by() {
+        let mut soa: Soa2<u32, String> = [5, 1, 4, 2, 3, 9, 0].iter().map(|i| (*i, i.to_string())).collect();
+
+        let (median, name) = soa.select_nth_unstable_by(3, |(a, _), (b, _)| a.cmp(b));
+        assert_eq!((*median, name.as_str()), (3, "3"));
+        assert!(soa.slices().0[..3].iter().all(|n| *n < 3));
+        assert!(soa.slices().0[4..].iter().all(|n| *n > 3));
+        assert!(soa.iter().all(|(n, s)| n.to_string() == *s));
+    }
+
+    #[test]
+    fn binary_search() {
+        let soa: Soa2<u32, &str> = vec![(1, "a"), (3, "b"), (5, "c"), (7, "d")].into();
+
+        assert_eq!(soa.binary_search_by(|(n, _)| n.cmp(&5)), Ok(2));
+        assert_eq!(soa.binary_search_by(|(n, _)| n.cmp(&4)), Err(2));
+        assert_eq!(soa.binary_search_by_key(&"d", |(_, s)| *s), Ok(3));
+        assert_eq!(soa.binary_search_by_key(&0, |(n, _)| *n), Err(0));
+        assert_eq!(soa.binary_search_by_key(&8, |(n, _)| *n), Err(4));
+        assert_eq!(Soa2::<u32, u32>::new().binary_search_by(|_| Ordering::Less), Err(0));
+    }
+
+    #[test]
+    fn partition_point() {
+        let soa: Soa2<u32, char> = vec![(1, 'a'), (2, 'b'), (2, 'c'), (2, 'd'), (5, 'e')].into();
+
+        let lower = soa.partition_point(|(n, _)| *n < 2);
+        let upper = soa.partition_point(|(n, _)| *n <= 2);
+        assert_eq!((lower, upper), (1, 4));
+        assert_eq!(soa.partition_point(|_| true), 5);
+        assert_eq!(soa.partition_point(|_| false), 0);
+    }
+
+    #[test]
+    fn equal_range_by_key() {
+        let soa: Soa2<&str, u32> = vec![("a", 1), ("b", 1), ("b", 2), ("b", 3), ("d", 4)].into();
+
+        assert_eq!(soa.equal_range_by_key(&"b", |(key, _)| *key), 1..4);
+        assert_eq!(soa.equal_range_by_key(&"c", |(key, _)| *key), 4..4);
+        assert_eq!(soa.equal_range_by_key(&"e", |(key, _)| *key), 5..5);
+
+        let range = soa.equal_range_by_key(&"b", |(key, _)| *key);
+        assert_eq!(soa.slices().1[range].iter().sum::<u32>(), 6);
+    }
+
+    #[test]
+    fn insert_sorted_by() {
+        let mut soa: Soa2<u32, char> = Soa2::new();
+        for tuple in [(3, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (4, 'e')] {
+            soa.insert_sorted_by(tuple, |(a, _), (b, _)| a.cmp(b));
+        }
+        assert_eq!(soa, [(1, 'b'), (1, 'd'), (2, 'c'), (3, 'a'), (4, 'e')][..]);
+        assert_eq!(soa.insert_sorted_by((0, 'f'), |(a, _), (b, _)| a.cmp(b)), 0);
+    }
+
+    #[test]
+    fn is_sorted() {
+        let mut soa: Soa2<u8, f32> = vec![(1, 2.0), (1, 3.0), (2, 0.0)].into();
+        assert!(soa.is_sorted());
+        assert!(!soa.is_sorted_by(|(_, a), (_, b)| a <= b));
+
+        soa.push((2, f32::NAN));
+        assert!(!soa.is_sorted());
+        assert!(soa.is_sorted_by(|(a, _), (b, _)| a <= b));
+        assert!(Soa2::<u8, f32>::new().is_sorted());
+    }
+
+    #[test]
+    fn arg_sort_unstable_by() {
+        let soa: Soa2<u8, char> = vec![(3, 'a'), (1, 'b'), (2, 'c')].into();
+        let indices = soa.arg_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(indices, [1, 2, 0]);
+        assert_eq!(soa, [(3, 'a'), (1, 'b'), (2, 'c')][..]);
+
+        let side_table = ["x", "y", "z"];
+        let sorted: Vec<_> = indices.iter().map(|i| side_table[*i]).collect();
+        assert_eq!(sorted, ["y", "z", "x"]);
+    }
+
+    #[test]
+    fn apply_permutation() {
+        let mut soa: Soa2<u8, String> = vec![(3, "c".to_owned()), (1, "a".to_owned()), (2, "b".to_owned()), (0, "z".to_owned())].into();
+        let mut other: Soa2<u8, String> = soa.clone();
+
+        let indices = soa.arg_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        soa.apply_permutation(&indices);
+        other.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(soa, other);
+
+        soa.apply_permutation(&[3, 2, 1, 0]);
+        assert_eq!(soa.slices().0, [3, 2, 1, 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn apply_permutation_duplicate() {
+        let mut soa: Soa2<u8, u8> = vec![(0, 0), (1, 1)].into();
+        soa.apply_permutation(&[1, 1]);
+    }
+
+    #[test]
+    fn sort_unstable_by_key_radix() {
+        let mut soa: Soa2<i32, String> = [300, -5, 0, 7, -70_000, 300, i32::MAX, i32::MIN, 1].iter().map(|n| (*n, n.to_string())).collect();
+        let mut expected: Vec<(i32, String)> = soa.clone().into();
+        expected.sort_unstable();
+
+        soa.sort_unstable_by_key_radix(|(n, _)| *n);
+        assert_eq!(soa, expected);
+
+        let mut soa: Soa2<u8, u64> = vec![(2, 0), (0, 1), (1, 2)].into();
+        soa.sort_unstable_by_key_radix(|(_, n)| u64::MAX - n);
+        assert_eq!(soa.slices().0, [1, 0, 2]);
+    }
+
+    #[test]
+    fn sort_by_key() {
+        let mut soa: Soa2<u8, char> = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')].into();
+        soa.sort_by_key(|(n, _)| *n);
+        assert_eq!(soa, [(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')][..]);
+    }
+
+    #[test]
+    fn drops() {
+        let td = TestDrop::new();
+        let (id, item) = td.new_item();
+        {
+            let mut soa = Soa2::new();
+            soa.push((1.0, item));
+
+            // Did not drop when moved into the vec
+            td.assert_no_drop(id);
+
+            // Did not drop through resizing the vec.
+            for _ in 0..50 {
+                soa.push((2.0, td.new_item().1));
+            }
+            td.assert_no_drop(id);
+        }
+        // Dropped with the vec
+        td.assert_drop(id);
+    }
+
+    #[test]
+    fn with_capacity() {
+        let mut soa = Soa3::with_capacity(10);
+        let (a, _, _) = soa.slices();
+        let ptr = a.as_ptr();
+
+        // Pushing up to the requested capacity must not reallocate.
+        for i in 0..10 {
+            soa.push((i, i as f32, i as u8));
+        }
+        let (a, b, c) = soa.slices();
+        assert_eq!(ptr, a.as_ptr());
+        assert_eq!(a[9], 9);
+        assert_eq!(b[9], 9.0);
+        assert_eq!(c[9], 9);
+    }
+
+    #[test]
+    fn reserve() {
+        let mut soa = Soa2::new();
+        soa.push((1u8, 1u64));
+
+        soa.reserve_exact(9);
+        let (a, _) = soa.slices();
+        let ptr = a.as_ptr();
+        for i in 0..9 {
+            soa.push((i, i as u64));
+        }
+        assert_eq!(ptr, soa.slices().0.as_ptr());
+
+        // Already has room, so this is a no-op
+        soa.pop();
+        soa.reserve(1);
+        assert_eq!(ptr, soa.slices().0.as_ptr());
+
+        soa.reserve(100);
+        assert_eq!(soa.len(), 9);
+        assert_eq!(soa.get(0), Some((&1, &1)));
+        assert_eq!(soa.get(8), Some((&7, &7)));
+    }
+
+    #[test]
+    fn try_reserve() {
+        let mut soa = Soa2::<u32, u64>::new();
+        assert_eq!(soa.try_reserve(10), Ok(()));
+        soa.push((1, 2));
+
+        assert_eq!(soa.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(soa.try_reserve_exact(usize::MAX / 4), Err(TryReserveError::CapacityOverflow));
+
+        // Failed reservations leave the soa untouched
+        assert_eq!(soa.len(), 1);
+        assert_eq!(soa.get(0), Some((&1, &2)));
+    }
+
+    #[test]
+    fn try_with_capacity() {
+        let soa = Soa2::<u32, u8>::try_with_capacity(10).unwrap();
+        assert_eq!(soa.capacity(), 10);
+        // The combined size of the fields would exceed isize::MAX bytes
+        assert_eq!(Soa2::<u32, u8>::try_with_capacity(isize::MAX as usize / 4).err(), Some(TryReserveError::CapacityOverflow));
+        assert_eq!(Soa2::<u64, u64>::try_with_capacity(usize::MAX / 8).err(), Some(TryReserveError::CapacityOverflow));
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity overflow")]
+    fn with_capacity_overflow() {
+        Soa2::<u64, u64>::with_capacity(usize::MAX / 8);
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut soa = Soa3::new();
+        for i in 0..100 {
+            soa.push((i, i as u8, i as f64));
+        }
+        while soa.len() > 10 {
+            soa.pop();
+        }
+        soa.shrink_to_fit();
+        assert_eq!(soa.len(), 10);
+        assert_eq!(soa.get(9), Some((&9, &9, &9.0)));
+
+        soa.clear();
+        soa.shrink_to_fit();
+        soa.push((1, 2, 3.0));
+        assert_eq!(soa.get(0), Some((&1, &2, &3.0)));
+    }
+
+    #[test]
+    fn shrink_to() {
+        let mut soa = Soa2::with_capacity(100);
+        soa.push((1, 'a'));
+        soa.shrink_to(10);
+
+        // Stays within the remaining capacity
+        let ptr = soa.slices().0.as_ptr();
+        for i in 0..9 {
+            soa.push((i, 'b'));
+        }
+        assert_eq!(ptr, soa.slices().0.as_ptr());
+        assert_eq!(soa.get(0), Some((&1, &'a')));
+
+        // Never shrinks below len
+        soa.shrink_to(0);
+        assert_eq!(soa.len(), 10);
+        assert_eq!(soa.get(9), Some((&8, &'b')));
+    }
+
+    #[test]
+    fn capacity() {
+        let mut soa = Soa2::<u8, u64>::new();
+        assert_eq!(soa.capacity(), 0);
+        assert_eq!(soa.allocated_bytes(), 0);
+
+        soa.reserve_exact(4);
+        assert_eq!(soa.capacity(), 4);
+        // 4 u8s, padded to the alignment of u64, followed by 4 u64s
+        assert_eq!(soa.allocated_bytes(), 8 + 32);
+
+        soa.push((1, 1));
+        soa.shrink_to_fit();
+        assert_eq!(soa.capacity(), 1);
+        assert_eq!(soa.allocated_bytes(), 8 + 8);
+    }
+
+    #[test]
+    fn ptrs() {
+        let mut soa = Soa2::<u8, u64>::with_capacity(2);
+        soa.push((1, 2));
+
+        let (a, b) = soa.as_ptrs();
+        assert_eq!(unsafe { (*a, *b) }, (1, 2));
+        // The second field starts after the first, aligned for u64
+        assert_eq!(b as usize - a as usize, 8);
+        assert_eq!(soa.layout().unwrap().align(), 8);
+
+        let (a, _) = soa.as_mut_ptrs();
+        unsafe { *a = 5 };
+        assert_eq!(soa.slices().0, &[5]);
+    }
+
+    #[test]
+    fn truncate() {
+        let td = TestDrop::new();
+        let mut soa = Soa2::new();
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            let (id, item) = td.new_item();
+            ids.push(id);
+            soa.push((i, item));
+        }
+
+        soa.truncate(20);
+        assert_eq!(soa.len(), 10);
+
+        soa.truncate(4);
+        assert_eq!(soa.len(), 4);
+        assert_eq!(soa.capacity(), 16);
+        for id in &ids[..4] {
+            td.assert_no_drop(*id);
+        }
+        for id in &ids[4..] {
+            td.assert_drop(*id);
+        }
+        assert_eq!(*soa.get(3).unwrap().0, 3);
+    }
+
+    #[test]
+    fn insert() {
+        let mut soa = Soa3::new();
+        soa.insert(0, (2, 'b', 2.0));
+        soa.insert(0, (0, 'a', 0.0));
+        soa.insert(1, (1, 'x', 1.0));
+        soa.insert(3, (3, 'c', 3.0));
+
+        assert_eq!(soa.slices(), (&[0, 1, 2, 3][..], &['a', 'x', 'b', 'c'][..], &[0.0, 1.0, 2.0, 3.0][..]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut soa = Soa2::new();
+        soa.push((1, 2));
+        soa.insert(2, (3, 4));
+    }
+
+    #[test]
+    fn remove() {
+        let mut soa = Soa2::new();
+        for i in 0..5 {
+            soa.push((i, i * 10));
+        }
+
+        assert_eq!(soa.remove(1), (1, 10));
+        assert_eq!(soa.remove(3), (4, 40));
+        assert_eq!(soa.remove(0), (0, 0));
+        assert_eq!(soa.slices(), (&[2, 3][..], &[20, 30][..]));
+    }
+
+    #[test]
+    fn retain() {
+        let td = TestDrop::new();
+        let mut soa = Soa3::new();
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            let (id, item) = td.new_item();
+            ids.push(id);
+            soa.push((i, item, i * 2));
+        }
+
+        soa.retain(|(i, _, _)| i % 3 == 0);
+
+        assert_eq!(soa.len(), 4);
+        assert_eq!(soa.slices().0, &[0, 3, 6, 9]);
+        assert_eq!(soa.slices().2, &[0, 6, 12, 18]);
+        for (i, id) in ids.iter().enumerate() {
+            if i % 3 == 0 {
+                td.assert_no_drop(*id);
+            } else {
+                td.assert_drop(*id);
+            }
+        }
+    }
+
+    #[test]
+    fn retain_mut() {
+        let mut soa = Soa2::new();
+        soa.push(('a', 1));
+        soa.push(('b', 3));
+        soa.push(('c', 2));
+
+        // Decrement the counters, removing the tuples that reach zero
+        soa.retain_mut(|(_, count)| {
+            *count -= 1;
+            *count > 0
+        });
+
+        assert_eq!(soa.slices(), (&['b', 'c'][..], &[2, 1][..]));
+    }
+
+    #[test]
+    fn drain() {
+        let mut soa = Soa2::new();
+        for i in 0..10 {
+            soa.push((i, i as f32));
+        }
+
+        let drained: Vec<_> = soa.drain(2..5).collect();
+        assert_eq!(drained, vec![(2, 2.0), (3, 3.0), (4, 4.0)]);
+        assert_eq!(soa.slices().0, &[0, 1, 5, 6, 7, 8, 9]);
+
+        {
+            let mut drain = soa.drain(1..=4);
+            assert_eq!(drain.len(), 4);
+            assert_eq!(drain.next(), Some((1, 1.0)));
+            assert_eq!(drain.next_back(), Some((7, 7.0)));
+        }
+        assert_eq!(soa.slices(), (&[0, 8, 9][..], &[0.0, 8.0, 9.0][..]));
+
+        soa.drain(..);
+        assert!(soa.is_empty());
+    }
+
+    #[test]
+    fn drain_drops() {
+        let td = TestDrop::new();
+        let mut soa = Soa2::new();
+        let mut ids = Vec::new();
+        for i in 0..6 {
+            let (id, item) = td.new_item();
+            ids.push(id);
+            soa.push((item, i));
+        }
+
+        // Unconsumed tuples are dropped with the iterator
+        soa.drain(1..3);
+        td.assert_drop(ids[1]);
+        td.assert_drop(ids[2]);
+        assert_eq!(soa.slices().1, &[0, 3, 4, 5]);
+
+        // Leaking the iterator loses tuples but never double drops
+        std::mem::forget(soa.drain(1..2));
+        assert_eq!(soa.slices().1, &[0]);
+        drop(soa);
+        td.assert_drop(ids[0]);
+        td.assert_no_drop(ids[3]);
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut soa = Soa2::new();
+        for i in 0..10 {
+            soa.push((i, i * 10));
+        }
+
+        let extracted: Vec<_> = soa.extract_if(|(i, _)| *i % 2 == 0).collect();
+        assert_eq!(extracted, vec![(0, 0), (2, 20), (4, 40), (6, 60), (8, 80)]);
+        assert_eq!(soa.slices(), (&[1, 3, 5, 7, 9][..], &[10, 30, 50, 70, 90][..]));
+
+        // Dropping early keeps everything not yet extracted
+        {
+            let mut iter = soa.extract_if(|(i, _)| *i > 1);
+            assert_eq!(iter.next(), Some((3, 30)));
+        }
+        assert_eq!(soa.slices(), (&[1, 5, 7, 9][..], &[10, 50, 70, 90][..]));
+    }
+
+    #[test]
+    fn splice() {
+        let mut soa = Soa2::new();
+        for i in 0..5 {
+            soa.push((i, i as u8));
+        }
+
+        // Replace with more than was removed
+        let removed: Vec<_> = soa.splice(1..3, vec![(10, 10), (11, 11), (12, 12)]).collect();
+        assert_eq!(removed, vec![(1, 1), (2, 2)]);
+        assert_eq!(soa.slices(), (&[0, 10, 11, 12, 3, 4][..], &[0, 10, 11, 12, 3, 4][..]));
+
+        // Replace with less than was removed, without consuming the removed tuples
+        soa.splice(2..5, Some((20, 20)));
+        assert_eq!(soa.slices(), (&[0, 10, 20, 4][..], &[0, 10, 20, 4][..]));
+
+        // Pure insertion at the end
+        soa.splice(4.., vec![(30, 30), (31, 31)]);
+        assert_eq!(soa.slices().0, &[0, 10, 20, 4, 30, 31]);
+
+        // A size hint with a lower bound of 0, so the extra tuples are buffered
+        soa.splice(1..2, (40..44).filter(|_| true).map(|i| (i, i as u8)));
+        assert_eq!(soa.slices().0, &[0, 40, 41, 42, 43, 20, 4, 30, 31]);
+        assert_eq!(soa.slices().1, &[0, 40, 41, 42, 43, 20, 4, 30, 31]);
+    }
+
+    #[test]
+    fn splice_drops() {
+        let td = TestDrop::new();
+        let mut soa = Soa2::new();
+        for i in 0..4 {
+            soa.push((i, td.new_item().1));
+        }
+        soa.shrink_to_fit();
+
+        // Grows the full soa while moving the tail, and only drops the removed tuple
+        let replace_with: Vec<_> = (10..13).map(|i| (i, td.new_item().1)).collect();
+        soa.splice(1..2, replace_with);
+        assert_eq!(td.num_dropped_items(), 1);
+        assert_eq!(soa.slices().0, &[0, 10, 11, 12, 2, 3]);
+
+        drop(soa);
+        assert_eq!(td.num_dropped_items(), 7);
+    }
+
+    #[test]
+    fn split_off() {
+        let mut soa = Soa2::new();
+        for i in 0..5 {
+            soa.push((i, i.to_string()));
+        }
+
+        let tail = soa.split_off(3);
+        assert_eq!(soa.slices().0, &[0, 1, 2]);
+        assert_eq!(tail.slices().0, &[3, 4]);
+        assert_eq!(tail.slices().1, &["3".to_string(), "4".to_string()]);
+
+        let empty = soa.split_off(3);
+        assert!(empty.is_empty());
+        assert_eq!(soa.len(), 3);
+    }
+
+    #[test]
+    fn append() {
+        let mut a = Soa2::new();
+        let mut b = Soa2::with_capacity(8);
+        a.push((1, 'a'));
+        b.push((2, 'b'));
+        b.push((3, 'c'));
+
+        a.append(&mut b);
+        assert_eq!(a.slices(), (&[1, 2, 3][..], &['a', 'b', 'c'][..]));
+        assert!(b.is_empty());
+        assert_eq!(b.capacity(), 8);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut soa = Soa2::new();
+        for v in &[(1, 'a'), (1, 'a'), (1, 'b'), (2, 'b'), (2, 'b'), (2, 'b'), (1, 'a')] {
+            soa.push(*v);
+        }
+
+        soa.dedup();
+        assert_eq!(soa.slices(), (&[1, 1, 2, 1][..], &['a', 'b', 'b', 'a'][..]));
+    }
+
+    #[test]
+    fn dedup_by() {
+        let mut soa = Soa2::new();
+        for v in &[(1, 1), (2, 1), (3, 2), (4, 2), (5, 3)] {
+            soa.push(*v);
+        }
+
+        // Merge runs with the same second field into the first tuple of the run
+        soa.dedup_by(|(a, b), (prev_a, prev_b)| {
+            if b == prev_b {
+                *prev_a += *a;
+                true
+            } else {
+                false
+            }
+        });
+        assert_eq!(soa.slices(), (&[3, 7, 5][..], &[1, 2, 3][..]));
+    }
+
+    #[test]
+    fn dedup_by_key() {
+        let mut soa = Soa2::new();
+        for v in &[(1, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (3, 'e')] {
+            soa.push(*v);
+        }
+
+        soa.dedup_by_key(|(k, _)| *k);
+        assert_eq!(soa.slices(), (&[1, 2, 3][..], &['a', 'c', 'd'][..]));
+    }
+
+    #[test]
+    fn resize() {
+        let mut soa = Soa2::new();
+        soa.push((1, "a".to_string()));
+
+        soa.resize(3, (0, "b".to_string()));
+        assert_eq!(soa.slices().0, &[1, 0, 0]);
+        assert_eq!(soa.slices().1, &["a", "b", "b"]);
+
+        soa.resize(1, (5, "c".to_string()));
+        assert_eq!(soa.slices().0, &[1]);
+    }
+
+    #[test]
+    fn resize_with() {
+        let mut soa = Soa2::new();
+        let mut next = 0;
+        soa.resize_with(3, || {
+            next += 1;
+            (next, vec![next])
+        });
+        assert_eq!(soa.slices().0, &[1, 2, 3]);
+        assert_eq!(soa.slices().1, &[vec![1], vec![2], vec![3]]);
+
+        soa.resize_with(1, || unreachable!());
+        assert_eq!(soa.len(), 1);
+    }
+
+    #[test]
+    fn extend_from_within() {
+        let mut soa = Soa2::new();
+        soa.push((1, "a".to_string()));
+        soa.push((2, "b".to_string()));
+        soa.push((3, "c".to_string()));
+
+        soa.extend_from_within(1..);
+        soa.extend_from_within(..1);
+        assert_eq!(soa.slices().0, &[1, 2, 3, 2, 3, 1]);
+        assert_eq!(soa.slices().1, &["a", "b", "c", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn swap() {
+        let mut soa = Soa3::new();
+        soa.push((1, 'a', "x"));
+        soa.push((2, 'b', "y"));
+        soa.push((3, 'c', "z"));
+
+        soa.swap(0, 2);
+        soa.swap(1, 1);
+        assert_eq!(soa.slices(), (&[3, 2, 1][..], &['c', 'b', 'a'][..], &["z", "y", "x"][..]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_out_of_bounds() {
+        let mut soa = Soa2::new();
+        soa.push((1, 2));
+        soa.swap(0, 1);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut soa = Soa2::new();
+        for i in 0..5 {
+            soa.push((i, i as f32));
+        }
+
+        soa.reverse();
+        assert_eq!(soa.slices(), (&[4, 3, 2, 1, 0][..], &[4.0, 3.0, 2.0, 1.0, 0.0][..]));
+    }
+
+    #[test]
+    fn rotate() {
+        let mut soa = Soa2::new();
+        for i in 0..5 {
+            soa.push((i, (b'a' + i) as char));
+        }
+
+        soa.rotate_left(2);
+        assert_eq!(soa.slices(), (&[2, 3, 4, 0, 1][..], &['c', 'd', 'e', 'a', 'b'][..]));
+
+        soa.rotate_right(3);
+        assert_eq!(soa.slices(), (&[4, 0, 1, 2, 3][..], &['e', 'a', 'b', 'c', 'd'][..]));
+    }
+
+    #[test]
+    fn first_last() {
+        let mut soa = Soa2::new();
+        assert_eq!(soa.first(), None);
+        assert_eq!(soa.last_mut(), None);
+
+        soa.push((1, 'a'));
+        soa.push((2, 'b'));
+        assert_eq!(soa.first(), Some((&1, &'a')));
+        assert_eq!(soa.last(), Some((&2, &'b')));
+
+        *soa.first_mut().unwrap().0 = 10;
+        *soa.last_mut().unwrap().1 = 'z';
+        assert_eq!(soa.slices(), (&[10, 2][..], &['a', 'z'][..]));
+    }
+
+    #[test]
+    fn push_within_capacity() {
+        let mut soa = Soa2::new();
+        assert_eq!(soa.push_within_capacity((1, 'a')), Err((1, 'a')));
+
+        soa.reserve_exact(1);
+        assert_eq!(soa.push_within_capacity((1, 'a')), Ok(()));
+        assert_eq!(soa.push_within_capacity((2, 'b')), Err((2, 'b')));
+        assert_eq!(soa.slices(), (&[1][..], &['a'][..]));
+    }
+
+    #[test]
+    fn spare_capacity() {
+        let mut soa = Soa2::<u32, u8>::with_capacity(4);
+        {
+            let (a, b) = soa.spare_capacity_mut();
+            assert_eq!(a.len(), 4);
+            assert_eq!(b.len(), 4);
+            for i in 0..3 {
+                a[i] = MaybeUninit::new(i as u32);
+                b[i] = MaybeUninit::new(i as u8 * 2);
+            }
+        }
+        unsafe { soa.set_len(3) };
+
+        assert_eq!(soa.slices(), (&[0, 1, 2][..], &[0, 2, 4][..]));
+        assert_eq!(soa.spare_capacity_mut().0.len(), 1);
+    }
+
+    #[test]
+    fn get() {
+        let mut soa = Soa2::new();
+        soa.push((1, 'a'));
+        soa.push((2, 'b'));
+
+        assert_eq!(soa.get(1), Some((&2, &'b')));
+        assert_eq!(soa.get(2), None);
+        assert_eq!(unsafe { soa.get_unchecked(0) }, (&1, &'a'));
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut soa = Soa2::new();
+        soa.push((1, 'a'));
+        soa.push((2, 'b'));
+
+        {
+            let (n, c) = soa.get_mut(1).unwrap();
+            *n += 10;
+            *c = 'z';
+        }
+        assert!(soa.get_mut(2).is_none());
+        assert_eq!(soa.slices(), (&[1, 12][..], &['a', 'z'][..]));
+    }
+
+    #[test]
+    fn get_many_mut() {
+        let mut soa = Soa2::new();
+        for i in 0..4 {
+            soa.push((i, i as f32));
+        }
+
+        {
+            let [(a, _), (b, fb)] = soa.get_many_mut([3, 1]).unwrap();
+            std::mem::swap(a, b);
+            *fb = 10.0;
+        }
+        assert_eq!(soa.slices(), (&[0, 3, 2, 1][..], &[0.0, 10.0, 2.0, 3.0][..]));
+
+        assert!(soa.get_many_mut([0, 0]).is_none());
+        assert!(soa.get_many_mut([0, 4]).is_none());
+    }
+
+    #[test]
+    // The leaked allocation is never freed, which miri reports as an error
+    #[cfg_attr(miri, ignore)]
+    fn leak() {
+        let mut soa = Soa2::new();
+        soa.push((1, "a".to_string()));
+        soa.push((2, "b".to_string()));
+
+        let (a, b): (&'static mut [i32], &'static mut [String]) = soa.leak();
+        a[0] = 10;
+        assert_eq!(a, &[10, 2]);
+        assert_eq!(b, &["a", "b"]);
+    }
+
+    #[test]
+    fn raw_parts() {
+        let td = TestDrop::new();
+        let (id, item) = td.new_item();
+        let mut soa = Soa2::with_capacity(10);
+        soa.push((1, item));
+
+        let (ptrs, len, capacity) = soa.into_raw_parts();
+        assert_eq!((len, capacity), (1, 10));
+        td.assert_no_drop(id);
+
+        let soa = unsafe { Soa2::from_raw_parts(ptrs, len, capacity) };
+        assert_eq!(soa.slices().0, &[1]);
+        drop(soa);
+        td.assert_drop(id);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Cannot decompose a soa created with with_alignment")]
+    fn raw_parts_with_alignment() {
+        Soa2::<u8, u8>::with_alignment(64).into_raw_parts();
+    }
+
+    #[test]
+    fn clear() {
+        let td = TestDrop::new();
+        let mut soa = Soa3::new();
+        for i in 0..10 {
+            soa.push((i, td.new_item().1, i as f32));
+        }
+        let capacity = soa.capacity();
+
+        soa.clear();
+        assert!(soa.is_empty());
+        assert_eq!(soa.capacity(), capacity);
+        assert_eq!(td.num_dropped_items(), 10);
+    }
+
+    #[test]
+    fn default() {
+        #[derive(Default)]
+        struct World {
+            entities: Soa3<u32, f32, String>,
+        }
+
+        let world = World::default();
+        assert!(world.entities.is_empty());
+        assert_eq!(world.entities.capacity(), 0);
+    }
+
+    #[test]
+    fn debug() {
+        let mut soa = Soa2::new();
+        assert_eq!(format!("{:?}", soa), "[]");
+
+        soa.push((1, 'a'));
+        soa.push((2, 'b'));
+        assert_eq!(format!("{:?}", soa), "[(1, 'a'), (2, 'b')]");
+    }
+
+    #[test]
+    fn eq() {
+        let mut a = Soa2::new();
+        let mut b = Soa2::with_capacity(10);
+        assert_eq!(a, b);
+
+        a.push((1, 'a'));
+        assert_ne!(a, b);
+
+        b.push((1, 'a'));
+        assert_eq!(a, b);
+
+        b.push((2, 'b'));
+        a.push((2, 'c'));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn eq_tuples() {
+        let mut soa = Soa2::new();
+        soa.push((1, 'a'));
+        soa.push((2, 'b'));
+
+        assert_eq!(soa, vec![(1, 'a'), (2, 'b')]);
+        assert_eq!(soa, [(1, 'a'), (2, 'b')][..]);
+        assert_ne!(soa, vec![(1, 'a')]);
+        assert_ne!(soa, vec![(1, 'a'), (2, 'c')]);
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash_of<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut a = Soa2::new();
+        let mut b = Soa2::with_capacity(10);
+        a.push((1, "a"));
+        a.push((2, "b"));
+        b.push((1, "a"));
+        b.push((2, "b"));
+
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert_eq!(hash_of(&a), hash_of(&vec![(1, "a"), (2, "b")]));
+        b.pop();
+        assert_ne!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn ord() {
+        fn soa(values: &[(i32, char)]) -> Soa2<i32, char> {
+            let mut soa = Soa2::new();
+            for v in values {
+                soa.push(*v);
+            }
+            soa
+        }
+
+        assert!(soa(&[(1, 'a')]) < soa(&[(1, 'b')]));
+        assert!(soa(&[(1, 'z')]) < soa(&[(2, 'a')]));
+        assert!(soa(&[(1, 'a')]) < soa(&[(1, 'a'), (0, 'a')]));
+        assert_eq!(soa(&[(1, 'a')]).cmp(&soa(&[(1, 'a')])), Ordering::Equal);
+
+        let mut a = Soa2::new();
+        a.push((1.0, 'a'));
+        let mut b = Soa2::new();
+        b.push((f64::NAN, 'a'));
+        assert_eq!(a.partial_cmp(&b), None);
+    }
+
+    #[test]
+    fn rows() {
+        let mut soa = Soa3::new();
+        soa.push((1, 'a', "x"));
+        soa.push((2, 'b', "y"));
+
+        let row = soa.row(1);
+        assert_eq!((*row.0, *row.1, *row.2), (2, 'b', "y"));
+
+        let row = soa.row_mut(0);
+        *row.0 = 10;
+        *row.2 = "z";
+        assert_eq!(soa, vec![(10, 'a', "z"), (2, 'b', "y")]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn row_out_of_bounds() {
+        let soa = Soa2::<u8, u8>::new();
+        soa.row(0);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut soa = Soa2::new();
+        for i in 0..4 {
+            soa.push((i, i.to_string()));
+        }
+
+        let mut iter = soa.into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some((0, "0".to_string())));
+        assert_eq!(iter.next_back(), Some((3, "3".to_string())));
+        assert_eq!(iter.as_slices(), (&[1, 2][..], &["1".to_string(), "2".to_string()][..]));
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), vec![(1, "1".to_string()), (2, "2".to_string())]);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn into_iter_drops() {
+        let td = TestDrop::new();
+        let mut soa = Soa2::new();
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let (id, item) = td.new_item();
+            ids.push(id);
+            soa.push((i, item));
+        }
+
+        let mut iter = soa.into_iter();
+        let (_, first) = iter.next().unwrap();
+        td.assert_no_drop(ids[1]);
+        drop(iter);
+        td.assert_no_drop(ids[0]);
+        for id in &ids[1..] {
+            td.assert_drop(*id);
+        }
+        drop(first);
+        td.assert_drop(ids[0]);
+    }
+
+    #[test]
+    fn from_iter() {
+        let soa: Soa3<_, _, _> = (0..5).map(|i| (i, i * 2, i.to_string())).collect();
+        assert_eq!(soa.len(), 5);
+        assert_eq!(soa.capacity(), 5);
+        assert_eq!(soa.slices().1, &[0, 2, 4, 6, 8]);
+
+        let soa: Soa2<_, _> = (0..10).filter(|i| i % 3 == 0).map(|i| (i, ())).collect();
+        assert_eq!(soa.slices().0, &[0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn extend() {
+        let mut soa = Soa2::new();
+        soa.push((0, 'a'));
+
+        soa.extend(vec![(1, 'b'), (2, 'c'), (3, 'd')]);
+        assert_eq!(soa.capacity(), 4);
+        soa.extend((4..6).map(|i| (i, 'e')));
+        assert_eq!(soa, vec![(0, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (4, 'e'), (5, 'e')]);
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let mut soa = Soa2::new();
+        soa.push((1, "a".to_string()));
+        assert_send_sync(&soa);
+
+        let soa = std::thread::spawn(move || {
+            soa.push((2, "b".to_string()));
+            soa
+        })
+        .join()
+        .unwrap();
+        assert_eq!(soa.len(), 2);
+    }
+
+    #[test]
+    fn from_vec() {
+        let soa = Soa2::from(vec![(1, "a".to_string()), (2, "b".to_string()), (3, "c".to_string())]);
+        assert_eq!(soa.capacity(), 3);
+        assert_eq!(soa.slices().0, &[1, 2, 3]);
+        assert_eq!(soa.slices().1, &["a", "b", "c"]);
+    }
+
+    #[test]
+    fn from_array() {
+        let soa = Soa3::from([(1, 'a', "x"), (2, 'b', "y")]);
+        assert_eq!(soa.capacity(), 2);
+        assert_eq!(soa, vec![(1, 'a', "x"), (2, 'b', "y")]);
+
+        let empty: Soa2<u8, u8> = [].into();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn try_from_slices() {
+        let soa = Soa2::try_from((&[1, 2, 3][..], &["a", "b", "c"][..])).unwrap();
+        assert_eq!(soa.capacity(), 3);
+        assert_eq!(soa, vec![(1, "a"), (2, "b"), (3, "c")]);
+
+        let names = vec!["a".to_string(), "b".to_string()];
+        let soa = Soa3::try_from_slices((&[1, 2], &names, &[1.0, 2.0])).unwrap();
+        assert_eq!(soa.slices().1, &names[..]);
+
+        assert_eq!(Soa2::try_from_slices((&[1, 2], &[1])), Err(LengthMismatchError));
+        assert_eq!(Soa3::try_from_slices((&[1], &[1], &[1, 2])), Err(LengthMismatchError));
+    }
+
+    #[test]
+    fn into_vec() {
+        let mut soa = Soa2::with_capacity(10);
+        soa.push((1, "a".to_string()));
+        soa.push((2, "b".to_string()));
+
+        let vec: Vec<_> = soa.into();
+        assert_eq!(vec, vec![(1, "a".to_string()), (2, "b".to_string())]);
+        assert_eq!(vec.capacity(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_rows() {
+        let mut soa = Soa3::new();
+        soa.push((1, 'a', "x".to_string()));
+        soa.push((2, 'b', "y".to_string()));
 
-			/// Sorts the soa keeping related data together.
-			pub fn sort_unstable_by<F: FnMut((&$t1 $(, &$ts)*), (&$t1 $(, &$ts)*))->Ordering>(&mut self, mut f: F) {
-				if self.len < 2 {
-					return;
-				}
-				let mut indices = acquire(0..self.len);
+        let json = serde_json::to_string(&soa).unwrap();
+        assert_eq!(json, r#"[[1,"a","x"],[2,"b","y"]]"#);
 
-				indices.sort_unstable_by(|a, b| unsafe {
-					f(
-						(&*self.$t1.as_ptr().add(*a) $(, &*self.$ts.as_ptr().add(*a))*, ),
-						(&*self.$t1.as_ptr().add(*b) $(, &*self.$ts.as_ptr().add(*b))*, ),
-					)});
+        let back: Soa3<i32, char, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, soa);
+    }
 
-				// Example
-				// c b d e a
-				// 4 1 0 2 3 // indices
-				// 2 1 3 4 0 // lookup
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_columns() {
+        let mut soa = Soa2::new();
+        soa.push((1, "x".to_string()));
+        soa.push((2, "y".to_string()));
 
-				let mut lookup = unsafe { acquire_uninitialized(self.len) };
-				for (i, index) in indices.iter().enumerate() {
-					lookup[*index] = i;
-				}
+        let mut json = Vec::new();
+        serde_columns::serialize(&soa, &mut serde_json::Serializer::new(&mut json)).unwrap();
+        assert_eq!(json, br#"[[1,2],["x","y"]]"#);
 
-				let ($t1 $(, $ts)*) = self.slices_mut();
+        let back: Soa2<i32, String> = serde_columns::deserialize(&mut serde_json::Deserializer::from_slice(&json)).unwrap();
+        assert_eq!(back, soa);
 
-				for i in 0..indices.len() {
-					let dest = indices[i]; // The index that should go here
-					if i != dest {
-						// Swap
-						$t1.swap(i, dest);
-						$($ts.swap(i, dest);)*
+        let mismatched: Result<Soa2<i32, String>, _> = serde_columns::deserialize(&mut serde_json::Deserializer::from_str(r#"[[1,2],["x"]]"#));
+        assert!(mismatched.is_err());
+    }
 
-						// Account for swaps that already happened
-						indices[lookup[i]] = dest;
-						lookup[dest] = lookup[i];
-					}
-				}
-			}
-		}
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
 
-		struct $L {
-			layout: Layout,
-			$($ts: usize,)*
-		}
+        // Each tuple is preceded by an odd byte to continue, and integers are read as little endian
+        let bytes = [1, 5, 0x34, 0x12, 1, 6, 0x78, 0x56, 0];
+        let soa = Soa2::<u8, u16>::arbitrary_take_rest(Unstructured::new(&bytes)).unwrap();
+        assert_eq!(soa.len(), 2);
+        assert_eq!(soa.slices(), (&[5, 6][..], &[0x1234, 0x5678][..]));
 
+        let bytes = [1, 7, 0, 0, 0, 1, 0x61, 0, 0, 0, 1, 8, 0, 0, 0, 0, 0x62, 0, 0, 0, 0];
+        let mut u = Unstructured::new(&bytes);
+        let soa = Soa3::<u32, bool, char>::arbitrary(&mut u).unwrap();
+        assert_eq!(soa.len(), 2);
+        assert_eq!(soa.slices(), (&[7, 8][..], &[true, false][..], &['a', 'b'][..]));
+        assert!(u.is_empty());
+    }
 
-		impl<$t1: Sized $(, $ts: Sized)*> Drop for $name<$t1 $(, $ts)*> {
-			fn drop(&mut self) {
-				self.clear(); // Drop owned items
-				self.dealloc()
-			}
-		}
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn swap_remove_matches_vec(
+            soa in strategy::soa2(proptest::prelude::any::<u8>(), proptest::prelude::any::<i64>(), 1..50),
+            index in 0usize..50,
+        ) {
+            let mut soa = soa;
+            let mut model: Vec<(u8, i64)> = soa.clone().into();
+            let index = index % soa.len();
 
+            assert_eq!(soa.swap_remove(index), model.swap_remove(index));
+            assert_eq!(soa, model);
+        }
+    }
 
-		impl<$t1: Clone + Sized $(, $ts: Clone + Sized)*> Clone for $name<$t1 $(, $ts)*> {
-			fn clone(&self) -> Self {
-				let capacity = self.len;
-				if capacity == 0 {
-					Self::new()
-				} else {
-					let ($t1 $(,$ts)*) = Self::alloc(capacity);
+    #[test]
+    #[cfg(feature = "rkyv")]
+    fn rkyv_archive() {
+        use rkyv::Deserialize;
 
-					unsafe {
-						for i in 0..self.len {
-							write($t1.as_ptr().add(i), (&*(self.$t1.as_ptr().add(i))).clone());
-						}
-						$(
-							for i in 0..self.len {
-								write($ts.as_ptr().add(i), (&*(self.$ts.as_ptr().add(i))).clone());
-							}
-						)*
-					}
+        let soa: Soa2<u32, u64> = vec![(1, 10), (2, 20), (3, 30)].into();
+        let bytes = rkyv::to_bytes::<_, 256>(&soa).unwrap();
+        let archived = unsafe { rkyv::archived_root::<Soa2<u32, u64>>(&bytes) };
 
-					Self {
-						capacity,
-						len: self.len,
-						$t1: $t1,
-						$($ts: $ts,)*
-						_marker: (PhantomData $(, PhantomData::<$ts>)*),
-					}
-				}
-			}
-		}
+        assert_eq!(archived.len(), 3);
+        let (a, b) = archived.slices();
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(b, [10, 20, 30]);
 
-		impl<$t1: Sized $(, $ts: Sized)*> Default for $name<$t1 $(, $ts)*> {
-			fn default() -> Self { Self::new() }
-		}
-	};
-}
+        let back: Soa2<u32, u64> = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        assert_eq!(back, soa);
+    }
 
-soa!(Soa2, _2, T1, T2);
-soa!(Soa3, _3, T1, T2, T3);
-soa!(Soa4, _4, T1, T2, T3, T4);
-soa!(Soa5, _5, T1, T2, T3, T4, T5);
-soa!(Soa6, _6, T1, T2, T3, T4, T5, T6);
-soa!(Soa7, _7, T1, T2, T3, T4, T5, T6, T7);
-soa!(Soa8, _8, T1, T2, T3, T4, T5, T6, T7, T8);
+    #[test]
+    fn column() {
+        let mut soa: Soa3<u8, String, u32> = vec![(1, "a".to_owned(), 10), (2, "b".to_owned(), 20)].into();
+        assert_eq!(Column::<1>::column(&soa), ["a", "b"]);
+        Column::<2>::column_mut(&mut soa)[1] = 30;
+        assert_eq!(Column::<2>::column(&soa), [10, 30]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use testdrop::TestDrop;
+        // Generic over the field index
+        fn double<const I: usize, S: Column<I, Item = u32>>(soa: &mut S) {
+            for value in soa.column_mut() {
+                *value *= 2;
+            }
+        }
+        double::<2, _>(&mut soa);
+        assert_eq!(Column::<2>::column(&soa), [20, 60]);
+    }
 
     #[test]
-    fn layouts_do_not_overlap() {
-        // Trying with both (small, large) and (large, small) to ensure nothing bleeds into anything else.
-        // This verifies we correctly chunk the slices from the larger allocations.
-        let mut soa_ab = Soa2::new();
-        let mut soa_ba = Soa2::new();
+    #[cfg(feature = "bytemuck")]
+    fn column_bytes() {
+        let mut soa: Soa2<u16, u8> = vec![(0x0102, 3), (0x0405, 6)].into();
+        assert_eq!(soa.column_bytes::<1>(), [3, 6]);
+        assert_eq!(soa.column_bytes::<0>().len(), 4);
+        soa.column_bytes_mut::<1>()[0] = 7;
+        assert_eq!(soa.slices().1, [7, 6]);
+    }
 
-        fn ab(v: usize) -> (u8, f64) {
-            (v as u8, 200.0 + ((v as f64) / 200.0))
+    #[test]
+    fn iter() {
+        let soa: Soa3<u8, String, u32> = vec![(1, "a".to_owned(), 10), (2, "b".to_owned(), 20), (3, "c".to_owned(), 30)].into();
+
+        let mut iter = soa.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some((&1, &"a".to_owned(), &10)));
+        assert_eq!(iter.next_back(), Some((&3, &"c".to_owned(), &30)));
+        assert_eq!(iter.len(), 1);
+
+        let mut sum = 0;
+        for (a, _, c) in &soa {
+            sum += *a as u32 + c;
         }
+        assert_eq!(sum, 66);
+    }
 
-        fn ba(v: usize) -> (f64, u8) {
-            (15.0 + ((v as f64) / 16.0), (200 - v) as u8)
+    #[test]
+    fn iter_copied_cloned() {
+        let soa: Soa2<u32, String> = vec![(1, "a".to_owned()), (2, "b".to_owned())].into();
+
+        let cloned: Vec<(u32, String)> = soa.iter_cloned().rev().collect();
+        assert_eq!(cloned, [(2, "b".to_owned()), (1, "a".to_owned())]);
+
+        let copied: Soa2<u32, u32> = vec![(1, 2), (3, 4)].into();
+        assert_eq!(copied.iter_copied().map(|(a, b)| a * b).sum::<u32>(), 14);
+        assert_eq!(soa.len(), 2);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut soa: Soa2<u32, String> = vec![(1, "a".to_owned()), (2, "b".to_owned()), (3, "c".to_owned())].into();
+
+        for (n, s) in soa.iter_mut() {
+            *n *= 10;
+            s.push('!');
         }
+        let mut iter = (&mut soa).into_iter();
+        let (last, _) = iter.next_back().unwrap();
+        *last += 1;
+        assert_eq!(iter.len(), 2);
 
-        // Combined with the tests inside, also verifies that we are copying the data on grow correctly.
-        for i in 0..100 {
-            soa_ab.push(ab(i));
-            let (a, b) = soa_ab.slices();
-            assert_eq!(i + 1, a.len());
-            assert_eq!(i + 1, b.len());
-            assert_eq!(ab(0).0, a[0]);
-            assert_eq!(ab(0).1, b[0]);
-            assert_eq!(ab(i).0, a[i]);
-            assert_eq!(ab(i).1, b[i]);
+        assert_eq!(soa.slices().0, [10, 20, 31]);
+        assert_eq!(soa.slices().1, ["a!", "b!", "c!"]);
+    }
 
-            soa_ba.push(ba(i));
-            let (b, a) = soa_ba.slices();
-            assert_eq!(i + 1, a.len());
-            assert_eq!(i + 1, b.len());
-            assert_eq!(ba(0).0, b[0]);
-            assert_eq!(ba(0).1, a[0]);
-            assert_eq!(ba(i).0, b[i]);
-            assert_eq!(ba(i).1, a[i]);
+    #[test]
+    fn iters_mut() {
+        let mut soa: Soa3<u32, u32, bool> = vec![(1, 0, true), (2, 0, false), (3, 0, true)].into();
+
+        let (a, mut b, c) = soa.iters_mut();
+        for (a, c) in a.zip(c) {
+            let b = b.next().unwrap();
+            if *c {
+                *b = *a * 2;
+            }
+            *a += 1;
         }
+
+        assert_eq!(soa.slices().0, [2, 3, 4]);
+        assert_eq!(soa.slices().1, [2, 0, 6]);
     }
 
     #[test]
-    fn sort() {
-        let mut soa = Soa3::new();
+    fn chunks() {
+        let soa: Soa2<u32, char> = (0..5).map(|i| (i, (b'a' + i as u8) as char)).collect();
 
-        soa.push((3, 'a', 4.0));
-        soa.push((1, 'b', 5.0));
-        soa.push((2, 'c', 6.0));
+        let mut chunks = soa.chunks(2);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.next(), Some((&[0, 1][..], &['a', 'b'][..])));
+        assert_eq!(chunks.next_back(), Some((&[4][..], &['e'][..])));
+        assert_eq!(chunks.next_back(), Some((&[2, 3][..], &['c', 'd'][..])));
+        assert_eq!(chunks.next(), None);
 
-        soa.sort_unstable_by(|(a1, _, _), (a2, _, _)| a1.cmp(a2));
+        assert_eq!(soa.chunks(5).count(), 1);
+        assert_eq!(Soa2::<u8, u8>::new().chunks(3).next(), None);
+    }
+
+    #[test]
+    fn chunks_exact_rchunks() {
+        let soa: Soa2<u32, u32> = (0..5).map(|i| (i, i * 10)).collect();
+
+        let mut chunks = soa.chunks_exact(2);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks.remainder(), (&[4][..], &[40][..]));
+        assert_eq!(chunks.next_back(), Some((&[2, 3][..], &[20, 30][..])));
+        assert_eq!(chunks.next(), Some((&[0, 1][..], &[0, 10][..])));
+        assert_eq!(chunks.next(), None);
 
-        assert_eq!(soa.get(0), (&1, &('b'), &5.0));
-        assert_eq!(soa.get(1), (&2, &('c'), &6.0));
-        assert_eq!(soa.get(2), (&3, &('a'), &4.0));
+        let firsts: Vec<_> = soa.rchunks(2).map(|(a, _)| a).collect();
+        assert_eq!(firsts, [&[3, 4][..], &[1, 2], &[0]]);
+        assert_eq!(soa.rchunks(2).next_back(), Some((&[0][..], &[0][..])));
+        assert_eq!(soa.rchunks(2).len(), 3);
     }
 
     #[test]
-    fn drops() {
+    fn windows() {
+        let soa: Soa2<i32, f32> = vec![(1, 0.5), (4, 1.0), (9, 1.5), (16, 2.0)].into();
+
+        let deltas: Vec<i32> = soa.windows(2).map(|(n, _)| n[1] - n[0]).collect();
+        assert_eq!(deltas, [3, 5, 7]);
+
+        let mut windows = soa.windows(3);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows.next_back(), Some((&[4, 9, 16][..], &[1.0, 1.5, 2.0][..])));
+        assert_eq!(windows.next(), Some((&[1, 4, 9][..], &[0.5, 1.0, 1.5][..])));
+        assert_eq!(windows.next(), None);
+
+        assert_eq!(soa.windows(5).next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "bumpalo")]
+    fn bump_frame() {
+        use crate::bump::FrameSoa2;
+        use bumpalo::Bump;
+
         let td = TestDrop::new();
-        let (id, item) = td.new_item();
+        let bump = Bump::new();
         {
-            let mut soa = Soa2::new();
-            soa.push((1.0, item));
+            let mut frame = FrameSoa2::new_in(&bump);
+            for i in 0..10 {
+                frame.push((i, td.new_item().1));
+            }
+            assert_eq!(frame.len(), 10);
+            assert!(frame.allocated_bytes() > 0);
+        }
+        // Dropping the frame does not run the destructors of its tuples
+        assert_eq!(td.num_dropped_items(), 0);
 
-            // Did not drop when moved into the vec
-            td.assert_no_drop(id);
+        let mut soa = Soa2::with_capacity_in(3, &bump);
+        soa.extend([(1, 'a'), (2, 'b'), (3, 'c')]);
+        let (numbers, chars) = soa.into_bump_slices();
+        numbers[0] = 4;
+        assert_eq!(numbers, &[4, 2, 3]);
+        assert_eq!(chars, &['a', 'b', 'c']);
+    }
 
-            // Did not drop through resizing the vec.
-            for _ in 0..50 {
-                soa.push((2.0, td.new_item().1));
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_iter() {
+        use rayon::prelude::*;
+
+        let soa: Soa3<u64, u64, bool> = (0..10_000).map(|i| (i, i * 2, i % 2 == 0)).collect();
+        let sum: u64 = soa.par_iter().map(|(a, b, even)| if *even { a + b } else { 0 }).sum();
+        assert_eq!(sum, soa.iter().filter(|(_, _, even)| **even).map(|(a, b, _)| a + b).sum::<u64>());
+        assert_eq!(soa.par_iter().len(), 10_000);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_iter_mut() {
+        use rayon::prelude::*;
+
+        let mut soa: Soa2<f32, f32> = (0..10_000).map(|i| (i as f32, 1.0)).collect();
+        soa.par_iter_mut().for_each(|(position, velocity)| *position += *velocity);
+        assert!(soa.iter().enumerate().all(|(i, (position, _))| *position == i as f32 + 1.0));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_sort_unstable_by() {
+        let mut soa: Soa3<u64, u64, String> = (0..10_000u64).map(|i| ((i * 7919) % 10_000, i, i.to_string())).collect();
+        soa.par_sort_unstable_by(|(a, _, _), (b, _, _)| a.cmp(b));
+        assert!(soa.iter().enumerate().all(|(i, (a, b, s))| *a == i as u64 && (b * 7919) % 10_000 == *a && *s == b.to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_extend() {
+        use rayon::prelude::*;
+
+        let mut soa: Soa2<u32, u64> = vec![(0, 0)].into();
+        soa.par_extend((1..1000u32).into_par_iter().map(|i| (i, i as u64 * 3)));
+        assert_eq!(soa.len(), 1000);
+        assert!(soa.iter().enumerate().all(|(i, (a, b))| *a as usize == i && *b == *a as u64 * 3));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_chunks() {
+        use rayon::prelude::*;
+
+        let mut soa: Soa2<f32, f32> = (0..1000).map(|i| (i as f32, 2.0)).collect();
+        soa.par_chunks_mut(64).for_each(|(a, b)| {
+            assert_eq!(a.len(), b.len());
+            for (a, b) in a.iter_mut().zip(b.iter()) {
+                *a *= b;
             }
-            td.assert_no_drop(id);
+        });
+        assert_eq!(soa.par_chunks(64).len(), 16);
+        let sum: f32 = soa.par_chunks(64).map(|(a, _)| a.iter().sum::<f32>()).sum();
+        assert_eq!(sum, 999_000.0);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn into_par_iter_collect() {
+        use rayon::prelude::*;
+
+        let soa: Soa2<u32, String> = (0..1000).map(|i| (i, i.to_string())).collect();
+        let doubled: Soa2<u32, String> = soa.par_iter().map(|(n, s)| (n * 2, s.clone())).collect();
+        assert_eq!(doubled.len(), 1000);
+        assert_eq!(doubled.capacity(), 1000);
+
+        let back: Soa2<u32, String> = doubled.into_par_iter().map(|(n, s)| (n / 2, s)).collect();
+        assert_eq!(back, soa);
+
+        // TestDrop items can't be sent between threads, so count the references instead
+        let counter = std::sync::Arc::new(());
+        let soa: Soa2<u32, _> = (0..100).map(|i| (i, counter.clone())).collect();
+        assert_eq!(soa.into_par_iter().filter(|(i, _)| i % 3 == 0).count(), 34);
+        assert_eq!(std::sync::Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn chunk_by() {
+        let soa: Soa2<&str, u32> = vec![("a", 1), ("a", 2), ("b", 3), ("c", 4), ("c", 5)].into();
+
+        let totals: Vec<(&str, u32)> = soa.chunk_by(|(a, _), (b, _)| a == b).map(|(keys, values)| (keys[0], values.iter().sum())).collect();
+        assert_eq!(totals, [("a", 3), ("b", 3), ("c", 9)]);
+
+        let mut runs = soa.chunk_by(|(_, a), (_, b)| a < b && b - a == 1 && *b != 3);
+        assert_eq!(runs.next_back(), Some((&["b", "c", "c"][..], &[3, 4, 5][..])));
+        assert_eq!(runs.next_back(), Some((&["a", "a"][..], &[1, 2][..])));
+        assert_eq!(runs.next(), None);
+        assert_eq!(Soa2::<u8, u8>::new().chunk_by(|_, _| true).next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunks_zero() {
+        Soa2::<u8, u8>::new().chunks(0);
+    }
+
+    #[test]
+    fn reserve_grows_fields() {
+        let mut soa: Soa4<u8, u64, u16, String> = Soa4::new();
+        for i in 0..100u64 {
+            soa.push((i as u8, i * 3, i as u16 * 2, i.to_string()));
+            soa.reserve(i as usize % 7);
         }
-        // Dropped with the vec
-        td.assert_drop(id);
+        assert!(soa.iter().enumerate().all(|(i, (a, b, c, d))| {
+            *a as usize == i && *b as usize == i * 3 && *c as usize == i * 2 && *d == i.to_string()
+        }));
     }
 
     #[test]
@@ -456,7 +5242,251 @@ mod tests {
 
         let dst = src.clone();
         assert_eq!(dst.len(), 2);
-        assert_eq!(dst.get(0), (&1.0, &2.0));
-        assert_eq!(dst.get(1), (&3.0, &4.0));
+        assert_eq!(dst.get(0), Some((&1.0, &2.0)));
+        assert_eq!(dst.get(1), Some((&3.0, &4.0)));
+    }
+
+    #[test]
+    fn clone_from() {
+        let mut src = Soa2::new();
+        src.push((1, "a".to_string()));
+        src.push((2, "b".to_string()));
+
+        let mut dst = Soa2::with_capacity(10);
+        dst.push((3, "c".to_string()));
+        let ptr = dst.slices().0.as_ptr();
+
+        dst.clone_from(&src);
+        assert_eq!(dst.slices(), src.slices());
+        assert_eq!(dst.slices().0.as_ptr(), ptr);
+        assert_eq!(dst.capacity(), 10);
+
+        // Grows when needed
+        let mut small = Soa2::new();
+        small.clone_from(&src);
+        assert_eq!(small.slices(), src.slices());
+    }
+
+    #[test]
+    fn zero_sized_fields() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        struct Marker;
+
+        // Every field zero-sized: nothing is allocated and the capacity is unbounded
+        let mut tags: Soa2<(), Marker> = Soa2::new();
+        assert_eq!(tags.capacity(), usize::MAX);
+        for _ in 0..1000 {
+            tags.push(((), Marker));
+        }
+        tags.shrink_to_fit();
+        assert_eq!(tags.len(), 1000);
+        assert_eq!(tags.capacity(), usize::MAX);
+        assert_eq!(tags.allocated_bytes(), 0);
+        assert_eq!(tags.pop(), Some(((), Marker)));
+        assert!(tags.try_reserve(usize::MAX).is_err());
+
+        // Mixed fields: zero-sized fields take up no space in the allocation
+        let mut soa: Soa3<u64, [u64; 0], Marker> = Soa3::with_capacity(4);
+        assert_eq!(soa.allocated_bytes(), 4 * size_of::<u64>());
+        for i in 0..10 {
+            soa.push((i, [], Marker));
+        }
+        soa.shrink_to_fit();
+        assert_eq!(soa.allocated_bytes(), 10 * size_of::<u64>());
+        assert_eq!(soa.slices().0, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(soa.slices().2.len(), 10);
+        assert_eq!(soa.remove(3), (3, [], Marker));
+    }
+
+    #[test]
+    fn with_alignment() {
+        let mut soa = Soa3::<f32, u8, f32>::with_alignment(64);
+        assert_eq!(soa.alignment(), 64);
+        let aligned = |soa: &Soa3<f32, u8, f32>| {
+            let (a, b, c) = soa.as_ptrs();
+            (a as usize).is_multiple_of(64) && (b as usize).is_multiple_of(64) && (c as usize).is_multiple_of(64)
+        };
+        for i in 0..100 {
+            soa.push((i as f32, i as u8, -(i as f32)));
+            assert!(aligned(&soa));
+        }
+        assert_eq!(soa.layout().unwrap().align(), 64);
+
+        let clone = soa.clone();
+        assert!(aligned(&clone));
+        let tail = soa.split_off(30);
+        assert!(aligned(&tail));
+        soa.shrink_to_fit();
+        assert!(aligned(&soa));
+        assert_eq!(soa.get(29), Some((&29.0, &29, &-29.0)));
+        assert_eq!(tail.get(0), Some((&30.0, &30, &-30.0)));
+
+        // Below the natural alignment the fields are still aligned as their types require
+        let mut soa = Soa2::<u8, u64>::with_alignment(2);
+        soa.push((1, 2));
+        assert!((soa.as_ptrs().1 as usize).is_multiple_of(8));
+    }
+
+    #[test]
+    fn with_alignment_zst() {
+        let aligned = |ptr: *const ()| (ptr as usize).is_multiple_of(64);
+
+        // An all zero-sized soa never allocates, so only has dangling pointers
+        let mut soa = Soa2::<(), ()>::with_alignment(64);
+        soa.push(((), ()));
+        let (a, b) = soa.as_ptrs();
+        assert!(aligned(a) && aligned(b));
+
+        // A zero-sized field takes no space in the allocation of the others
+        let mut soa = Soa2::<u8, ()>::with_alignment(64);
+        assert!(aligned(soa.as_ptrs().1));
+        for i in 0..10 {
+            soa.push((i, ()));
+            assert!(aligned(soa.as_ptrs().1));
+        }
+        soa.clear();
+        soa.shrink_to_fit();
+        assert!(aligned(soa.as_ptrs().0 as *const ()) && aligned(soa.as_ptrs().1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Alignment must be a power of two")]
+    fn with_alignment_not_power_of_two() {
+        Soa2::<u8, u8>::with_alignment(48);
+    }
+
+    #[test]
+    fn small_soa() {
+        let td = TestDrop::new();
+        let mut soa = SmallSoa2::<u32, _, 4>::new();
+        for i in 0..4 {
+            soa.push((i, td.new_item().1));
+        }
+        assert!(!soa.spilled());
+        assert_eq!(soa.capacity(), 4);
+        assert_eq!(soa.get(3).map(|(i, _)| *i), Some(3));
+
+        assert!(soa.pop().is_some());
+        assert_eq!(td.num_dropped_items(), 1);
+        soa.truncate(2);
+        assert_eq!(td.num_dropped_items(), 2);
+
+        // Pushing beyond the inline capacity moves the tuples to the heap
+        for i in 2..6 {
+            soa.push((i, td.new_item().1));
+        }
+        assert!(soa.spilled());
+        assert_eq!(soa.len(), 6);
+        assert_eq!(soa.slices().0, &[0, 1, 2, 3, 4, 5]);
+        *soa.get_mut(0).unwrap().0 = 10;
+        let heap = soa.into_soa();
+        assert_eq!(heap.slices().0, &[10, 1, 2, 3, 4, 5]);
+        drop(heap);
+        assert_eq!(td.num_dropped_items(), 8);
+
+        let inline = SmallSoa2::<u8, char, 3>::from_iter([(1, 'a'), (2, 'b')]);
+        assert_eq!(format!("{:?}", inline), "[(1, 'a'), (2, 'b')]");
+        assert_eq!(inline.clone(), inline);
+        // Converting from inline storage allocates exactly enough for the tuples
+        let heap = inline.into_soa();
+        assert_eq!(heap.capacity(), 2);
+        assert_eq!(heap, vec![(1, 'a'), (2, 'b')]);
+    }
+
+    #[test]
+    fn small_soa_spill() {
+        // With no inline capacity the first push spills, allocating only what it needs
+        let mut soa = SmallSoa2::<u8, u16, 0>::new();
+        assert!(!soa.spilled());
+        assert_eq!(soa.capacity(), 0);
+        soa.push((1, 10));
+        assert!(soa.spilled());
+        assert_eq!(soa.capacity(), 1);
+
+        // Filling the inline arrays exactly does not spill, and the next push spills to twice the inline capacity
+        let mut soa = SmallSoa2::<u8, u16, 3>::new();
+        for i in 0..3 {
+            soa.push((i, i as u16));
+        }
+        assert!(!soa.spilled());
+        assert_eq!(soa.capacity(), 3);
+        soa.push((3, 3));
+        assert!(soa.spilled());
+        assert_eq!(soa.capacity(), 6);
+        assert_eq!(soa.slices(), (&[0, 1, 2, 3][..], &[0, 1, 2, 3][..]));
+    }
+
+    #[test]
+    fn soa_array() {
+        let td = TestDrop::new();
+        let mut array = SoaArray2::<u32, _, 3>::new();
+        for i in 0..3 {
+            array.push((i, td.new_item().1));
+        }
+        assert!(array.is_full());
+        let (_, item) = td.new_item();
+        assert!(array.try_push((3, item)).is_err());
+        assert_eq!(td.num_dropped_items(), 1);
+
+        assert_eq!(array.slices().0, &[0, 1, 2]);
+        *array.get_mut(1).unwrap().0 = 10;
+        assert_eq!(array.get(1).map(|(i, _)| *i), Some(10));
+        assert!(array.pop().is_some());
+        assert_eq!(td.num_dropped_items(), 2);
+        drop(array);
+        assert_eq!(td.num_dropped_items(), 4);
+
+        let array = SoaArray2::<u8, char, 4>::from_iter([(1, 'a'), (2, 'b')]);
+        assert_eq!(format!("{:?}", array), "[(1, 'a'), (2, 'b')]");
+        assert_eq!(array.clone(), array);
+    }
+
+    #[test]
+    #[should_panic(expected = "Soa array is full")]
+    fn soa_array_full() {
+        let mut array = SoaArray2::<u8, u8, 1>::new();
+        array.push((1, 1));
+        array.push((2, 2));
+    }
+
+    #[test]
+    fn custom_allocator() {
+        use std::cell::Cell;
+
+        // Counts the live allocations made through it
+        #[derive(Clone, Copy)]
+        struct Counting<'a>(&'a Cell<isize>);
+
+        unsafe impl Allocator for Counting<'_> {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.0.set(self.0.get() + 1);
+                Global.allocate(layout)
+            }
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.0.set(self.0.get() - 1);
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let live = Cell::new(0);
+        {
+            let mut soa = Soa2::new_in(Counting(&live));
+            assert_eq!(live.get(), 0);
+            for i in 0..100 {
+                soa.push((i, i.to_string()));
+            }
+            assert_eq!(live.get(), 1);
+
+            let clone = soa.clone();
+            let tail = soa.split_off(50);
+            assert_eq!(live.get(), 3);
+            assert_eq!(tail.get(0), Some((&50, &"50".to_string())));
+            assert_eq!(clone, soa.iter().chain(tail.iter()).map(|(a, b)| (*a, b.clone())).collect::<Vec<_>>());
+
+            let exact = Soa2::<u8, u16, _>::with_capacity_in(10, Counting(&live));
+            assert_eq!(exact.capacity(), 10);
+            assert_eq!(live.get(), 4);
+        }
+        assert_eq!(live.get(), 0);
     }
 }